@@ -0,0 +1,114 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// links that checked out fine on an earlier run and when, so runs close together can skip
+/// them. only working links are kept, a broken one is always checked again.
+///
+/// the file is one link per line, the unix time it was last seen working, a tab and the url
+pub struct Cache {
+    path: PathBuf,
+    ttl: Duration,
+    verified: HashMap<String, u64>,
+}
+
+impl Cache {
+    /// reads the cache at path, a missing file is just an empty cache
+    pub fn load(path: &Path, ttl: Duration) -> io::Result<Self> {
+        let verified = match fs::read_to_string(path) {
+            Ok(content) => content
+                .lines()
+                .filter_map(|line| {
+                    let (time, url) = line.split_once('\t')?;
+                    Some((url.to_string(), time.parse().ok()?))
+                })
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Cache {
+            path: path.to_path_buf(),
+            ttl,
+            verified,
+        })
+    }
+
+    /// true when the link worked less than the ttl ago
+    pub fn is_fresh(&self, url: &str) -> bool {
+        self.verified
+            .get(url)
+            .is_some_and(|&time| now().saturating_sub(time) < self.ttl.as_secs())
+    }
+
+    pub fn record(&mut self, url: &str) {
+        self.verified.insert(url.to_string(), now());
+    }
+
+    /// writes the cache back, leaving out anything that has gone stale
+    pub fn save(&self) -> io::Result<()> {
+        let mut entries: Vec<(&String, &u64)> = self
+            .verified
+            .iter()
+            .filter(|(url, _)| self.is_fresh(url))
+            .collect();
+        entries.sort();
+        let content: String = entries
+            .into_iter()
+            .map(|(url, time)| format!("{}\t{}\n", time, url))
+            .collect();
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, content)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_links_are_checked_again_and_dropped() {
+        let dir = std::env::temp_dir().join(format!("brokenlinks-cache-{}", std::process::id()));
+        let path = dir.join("cache");
+        fs::create_dir_all(&dir).unwrap();
+        let old = now() - 120;
+        fs::write(
+            &path,
+            format!(
+                "{}\thttps://a.com/old\n{}\thttps://a.com/new\nnonsense\n",
+                old,
+                now()
+            ),
+        )
+        .unwrap();
+
+        let mut cache = Cache::load(&path, Duration::from_secs(60)).unwrap();
+        assert!(!cache.is_fresh("https://a.com/old"));
+        assert!(cache.is_fresh("https://a.com/new"));
+        assert!(!cache.is_fresh("https://a.com/other"));
+        cache.record("https://a.com/other");
+        cache.save().unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(!saved.contains("https://a.com/old"));
+        assert!(saved.contains("https://a.com/new"));
+        assert!(saved.contains("https://a.com/other"));
+    }
+
+    #[test]
+    fn missing_file_is_an_empty_cache() {
+        let path = std::env::temp_dir().join("brokenlinks-cache-that-isnt-there");
+        let cache = Cache::load(&path, Duration::from_secs(60)).unwrap();
+        assert!(!cache.is_fresh("https://a.com"));
+    }
+}
@@ -0,0 +1,393 @@
+use reqwest::{
+    blocking::{Client, Response},
+    header::{LOCATION, RETRY_AFTER},
+    redirect, Method, StatusCode,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+use url::Url;
+
+/// what came back from checking a single link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// anything in the 200s
+    Ok,
+    /// works, but only after a permanent redirect to this url, which the link could point at
+    /// instead
+    Redirected(String),
+    /// the server answered with something other than a success
+    Http(u16),
+    /// never got an answer
+    Failed(ErrorKind),
+    /// a link into the tree pointing at a file that isn't there
+    MissingFile,
+    /// the file is there but has no heading or id the part after # could mean
+    MissingAnchor,
+}
+
+/// why a request got no answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Timeout,
+    /// the host name doesn't resolve
+    Dns,
+    /// the connection went through but the certificate or handshake didn't
+    Tls,
+    Connect,
+    TooManyRedirects,
+    InvalidUrl,
+    Request,
+}
+
+impl Status {
+    pub fn is_broken(&self) -> bool {
+        !matches!(self, Status::Ok | Status::Redirected(_))
+    }
+
+    /// works but should still be looked at
+    pub fn is_warning(&self) -> bool {
+        matches!(self, Status::Redirected(_))
+    }
+
+    /// the kind of problem as a short snake_case name for the machine readable reports
+    pub fn category(&self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Redirected(_) => "redirect",
+            Status::Http(_) => "http_status",
+            Status::Failed(kind) => kind.category(),
+            Status::MissingFile => "missing_file",
+            Status::MissingAnchor => "missing_anchor",
+        }
+    }
+
+    pub fn code(&self) -> Option<u16> {
+        match self {
+            Status::Http(code) => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+impl ErrorKind {
+    pub fn category(self) -> &'static str {
+        match self {
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Dns => "dns",
+            ErrorKind::Tls => "tls",
+            ErrorKind::Connect => "connection",
+            ErrorKind::TooManyRedirects => "redirect_loop",
+            ErrorKind::InvalidUrl => "invalid_url",
+            ErrorKind::Request => "request",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            ErrorKind::Timeout => "timed out",
+            ErrorKind::Dns => "host not found",
+            ErrorKind::Tls => "tls error",
+            ErrorKind::Connect => "couldn't connect",
+            ErrorKind::TooManyRedirects => "too many redirects",
+            ErrorKind::InvalidUrl => "not a valid url",
+            ErrorKind::Request => "request failed",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Ok => write!(f, "ok"),
+            Status::Redirected(to) => write!(f, "moved to {}", to),
+            Status::Http(code) => match StatusCode::from_u16(*code)
+                .ok()
+                .and_then(|code| code.canonical_reason())
+            {
+                Some(reason) => write!(f, "{} {}", code, reason),
+                None => write!(f, "{}", code),
+            },
+            Status::Failed(reason) => write!(f, "{}", reason),
+            Status::MissingFile => write!(f, "no such file"),
+            Status::MissingAnchor => write!(f, "no such heading"),
+        }
+    }
+}
+
+/// most redirects followed for one link before giving up on it
+const MAX_REDIRECTS: usize = 10;
+
+/// longest a Retry-After is waited out for, servers asking for more get cut down to it so one
+/// link can't hold a worker for an hour
+pub const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+
+/// what a single url answered with, once retries are used up
+enum Answer {
+    Done(Status),
+    /// a 3xx with where to go next
+    Redirect(String, StatusCode),
+}
+
+/// how long to wait on a server and how hard to try before calling a link broken
+pub struct Policy {
+    pub connect_timeout: Duration,
+    /// for the whole request, from connecting to the end of the response. the blocking client
+    /// has no timeout for reads on their own, this is what stops a server that goes quiet
+    pub timeout: Duration,
+    /// extra tries after a timeout, a 429 or a 5xx
+    pub retries: u32,
+    /// wait before the first retry, doubled for every one after it
+    pub backoff: Duration,
+    /// a Retry-After longer than this gets cut down to it
+    pub max_wait: Duration,
+}
+
+/// checks links on a pool of worker threads sharing one client, so connections to the same
+/// host get reused
+pub struct Checker {
+    client: Client,
+    jobs: usize,
+    limiter: HostLimiter,
+    policy: Policy,
+}
+
+impl Checker {
+    /// jobs is how many requests can be in flight at once, rate is how many requests a second
+    /// any one host gets
+    pub fn new(jobs: usize, rate: f64, policy: Policy) -> Self {
+        let client = Client::builder()
+            .connect_timeout(policy.connect_timeout)
+            .timeout(policy.timeout)
+            // redirects are followed by hand so permanent ones can be told apart
+            .redirect(redirect::Policy::none())
+            .build()
+            .expect("the http client settings are valid");
+        Checker {
+            client,
+            jobs: jobs.max(1),
+            limiter: HostLimiter::new(rate),
+            policy,
+        }
+    }
+
+    /// checks every link and gives the statuses back in the same order as the links, no
+    /// matter which worker finished first
+    pub fn check_all(&self, links: &[String]) -> Vec<Status> {
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(vec![None; links.len()]);
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.min(links.len()) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(link) = links.get(index) else {
+                        break;
+                    };
+                    let status = self.check(link);
+                    results.lock().unwrap()[index] = Some(status);
+                });
+            }
+        });
+        results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|status| status.expect("every link is checked by a worker"))
+            .collect()
+    }
+
+    /// takes the link and sees if it actualy goes anywhere, following redirects. when the
+    /// page is there and every redirect from the start of the chain up to some point was
+    /// permanent, the link is reported as moved to that point
+    fn check(&self, link: &str) -> Status {
+        let Ok(mut url) = Url::parse(link) else {
+            return Status::Failed(ErrorKind::InvalidUrl);
+        };
+        let mut moved: Option<Url> = None;
+        let mut permanent = true;
+        for _ in 0..=MAX_REDIRECTS {
+            match self.request(url.as_str()) {
+                Answer::Redirect(location, code) => {
+                    let Ok(mut next) = url.join(&location) else {
+                        return Status::Failed(ErrorKind::InvalidUrl);
+                    };
+                    // the fragment never goes to the server, so it has to be carried over
+                    if next.fragment().is_none() {
+                        next.set_fragment(url.fragment());
+                    }
+                    permanent &= matches!(
+                        code,
+                        StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT
+                    );
+                    if permanent {
+                        moved = Some(next.clone());
+                    }
+                    url = next;
+                }
+                Answer::Done(Status::Ok) => {
+                    return moved.map_or(Status::Ok, |to| Status::Redirected(to.to_string()))
+                }
+                Answer::Done(status) => return status,
+            }
+        }
+        Status::Failed(ErrorKind::TooManyRedirects)
+    }
+
+    /// sends a single url without following redirects. a HEAD request comes first so the page
+    /// doesn't have to be downloaded, and a GET only when the server won't answer the HEAD with
+    /// a success or a redirect
+    fn request(&self, link: &str) -> Answer {
+        let mut method = Method::HEAD;
+        let mut retries = 0;
+        loop {
+            self.limiter.wait(link);
+            let (status, wait) = match self.client.request(method.clone(), link).send() {
+                Ok(response) if response.status().is_success() => return Answer::Done(Status::Ok),
+                Ok(response) if response.status().is_redirection() => {
+                    let location = response
+                        .headers()
+                        .get(LOCATION)
+                        .and_then(|location| location.to_str().ok());
+                    match location {
+                        Some(location) => {
+                            return Answer::Redirect(location.to_string(), response.status())
+                        }
+                        None => (Status::Http(response.status().as_u16()), None),
+                    }
+                }
+                Ok(response) => {
+                    let code = response.status();
+                    let retry = code == StatusCode::TOO_MANY_REQUESTS || code.is_server_error();
+                    (
+                        Status::Http(code.as_u16()),
+                        retry.then(|| retry_after(&response)),
+                    )
+                }
+                Err(e) => {
+                    let kind = failure(&e);
+                    let retry = kind == ErrorKind::Timeout;
+                    (Status::Failed(kind), retry.then_some(None))
+                }
+            };
+
+            if let Some(wait) = wait.filter(|_| retries < self.policy.retries) {
+                let backoff = self.policy.backoff * 2u32.saturating_pow(retries);
+                thread::sleep(wait.unwrap_or(backoff).min(self.policy.max_wait));
+                retries += 1;
+            } else if method == Method::HEAD && matches!(status, Status::Http(_)) {
+                // lots of servers answer HEAD with 403, 405 or worse while GET works fine. the GET
+                // gets its own retries
+                method = Method::GET;
+                retries = 0;
+            } else {
+                return Answer::Done(status);
+            }
+        }
+    }
+}
+
+/// how long the server asked us to wait, either in seconds or as a date. nothing for a wait
+/// that's already over, so the backoff is used instead of trying again straight away
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    let wait = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => httpdate::parse_http_date(value)
+            .ok()?
+            .duration_since(SystemTime::now())
+            .ok()?,
+    };
+    (!wait.is_zero()).then_some(wait)
+}
+
+/// reqwest only says whether it was a timeout or a connect error, what went wrong while
+/// connecting is in the messages of the errors underneath
+fn failure(e: &reqwest::Error) -> ErrorKind {
+    if e.is_timeout() {
+        ErrorKind::Timeout
+    } else if e.is_redirect() {
+        ErrorKind::TooManyRedirects
+    } else if e.is_connect() {
+        let mut messages = String::new();
+        let mut source = e.source();
+        while let Some(cause) = source {
+            messages.push_str(&cause.to_string().to_lowercase());
+            messages.push('\n');
+            source = cause.source();
+        }
+        if messages.contains("dns error") || messages.contains("failed to lookup address") {
+            ErrorKind::Dns
+        } else if ["tls", "ssl", "certificate", "handshake"]
+            .iter()
+            .any(|word| messages.contains(word))
+        {
+            ErrorKind::Tls
+        } else {
+            ErrorKind::Connect
+        }
+    } else if e.is_builder() {
+        ErrorKind::InvalidUrl
+    } else {
+        ErrorKind::Request
+    }
+}
+
+/// spaces out requests to the same host so a page full of links to one site doesn't hammer it
+struct HostLimiter {
+    interval: Duration,
+    /// earliest time the next request to each host may go out
+    next: Mutex<HashMap<String, Instant>>,
+}
+
+impl HostLimiter {
+    fn new(rate: f64) -> Self {
+        // a rate so small the interval doesn't fit in a Duration just means one request a day
+        let interval = if rate > 0.0 {
+            Duration::try_from_secs_f64(1.0 / rate)
+                .unwrap_or(Duration::MAX)
+                .min(Duration::from_secs(24 * 60 * 60))
+        } else {
+            Duration::ZERO
+        };
+        HostLimiter {
+            interval,
+            next: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// blocks until this link's host may be sent another request
+    fn wait(&self, link: &str) {
+        if self.interval.is_zero() {
+            return;
+        }
+        let Some(host) = Url::parse(link)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_owned))
+        else {
+            return;
+        };
+        // book a slot while holding the lock, then sleep without it so other hosts carry on
+        let slot = {
+            let mut next = self.next.lock().unwrap();
+            let now = Instant::now();
+            let slot = next.get(&host).map_or(now, |&at| at.max(now));
+            next.insert(host, slot + self.interval);
+            slot
+        };
+        let now = Instant::now();
+        if slot > now {
+            thread::sleep(slot - now);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{checker::Status, links::Link};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// one url in a file to swap for another. from and to are written the way the file needs them,
+/// url is the link as it reads
+struct Edit {
+    offset: usize,
+    from: String,
+    to: String,
+    url: String,
+}
+
+/// a file with its links rewritten
+pub struct Fixed {
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+    pub edits: usize,
+    /// urls that should have been rewritten but weren't where they were found
+    pub skipped: Vec<String>,
+}
+
+/// the http links that also work over https, for the caller to check. each one maps to its
+/// https version
+pub fn upgrade_candidates(results: &[(Link, Status)]) -> HashMap<String, String> {
+    results
+        .iter()
+        .filter(|(_, status)| *status == Status::Ok)
+        .filter_map(|(link, _)| {
+            let rest = link.url.strip_prefix("http://")?;
+            Some((link.url.clone(), format!("https://{}", rest)))
+        })
+        .collect()
+}
+
+/// rewrites every file with a link that moved permanently or can be upgraded to https. the
+/// files are read again and a link is only touched if it's still written where it was found,
+/// so a file edited since it was checked doesn't get mangled. the ones that aren't are given
+/// back as skipped. nothing is written yet
+pub fn fix(
+    results: &[(Link, Status)],
+    upgrades: &HashMap<String, String>,
+) -> Vec<Result<Fixed, String>> {
+    let mut edits: BTreeMap<&Path, Vec<Edit>> = BTreeMap::new();
+    for (link, status) in results {
+        let to = match status {
+            Status::Redirected(to) => to,
+            _ => match upgrades.get(&link.url) {
+                Some(to) => to,
+                None => continue,
+            },
+        };
+        edits.entry(&link.path).or_default().push(Edit {
+            offset: link.offset,
+            from: link.raw.clone(),
+            to: if link.html {
+                encode_entities(to)
+            } else {
+                to.clone()
+            },
+            url: link.url.clone(),
+        });
+    }
+
+    edits
+        .into_iter()
+        .map(|(path, mut edits)| {
+            let before = fs::read_to_string(path)
+                .map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+            let mut after = before.clone();
+            // from the end so earlier offsets stay right
+            edits.sort_by_key(|edit| std::cmp::Reverse(edit.offset));
+            let (edits, skipped): (Vec<Edit>, Vec<Edit>) = edits.into_iter().partition(|edit| {
+                !edit.from.is_empty()
+                    && before
+                        .get(edit.offset..)
+                        .is_some_and(|rest| rest.starts_with(&edit.from))
+            });
+            for edit in &edits {
+                after.replace_range(edit.offset..edit.offset + edit.from.len(), &edit.to);
+            }
+            Ok(Fixed {
+                path: path.to_path_buf(),
+                before,
+                after,
+                edits: edits.len(),
+                skipped: skipped.into_iter().rev().map(|edit| edit.url).collect(),
+            })
+        })
+        .collect()
+}
+
+/// a url made safe to put in an html attribute
+fn encode_entities(url: &str) -> String {
+    url.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+impl Fixed {
+    pub fn write(&self) -> io::Result<()> {
+        fs::write(&self.path, &self.after)
+    }
+
+    /// a unified diff of the change. links never span lines, so every changed line is its own
+    /// hunk and the line numbers are the same on both sides
+    pub fn diff(&self) -> String {
+        let mut out = String::new();
+        let name = self.path.display().to_string().replace('\\', "/");
+        let name = name.trim_start_matches("./");
+        let _ = writeln!(out, "--- a/{}\n+++ b/{}", name, name);
+        for (number, (old, new)) in self.before.lines().zip(self.after.lines()).enumerate() {
+            if old != new {
+                let _ = writeln!(
+                    out,
+                    "@@ -{} +{} @@\n-{}\n+{}",
+                    number + 1,
+                    number + 1,
+                    old,
+                    new
+                );
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::links::find_links;
+
+    /// a file of its own in the temp directory, so tests running at the same time don't meet
+    fn file(name: &str, content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("brokenlinks-fix-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn moved(path: &Path, to: &str) -> Vec<(Link, Status)> {
+        let content = fs::read_to_string(path).unwrap();
+        find_links(path, &content)
+            .into_iter()
+            .map(|link| (link, Status::Redirected(to.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn upgrade_candidates_are_working_http_links() {
+        let path = file(
+            "candidates.md",
+            "http://a.com/x https://b.com http://c.com\n",
+        );
+        let content = fs::read_to_string(&path).unwrap();
+        let links = find_links(&path, &content);
+        let results = vec![
+            (links[0].clone(), Status::Ok),
+            (links[1].clone(), Status::Ok),
+            (links[2].clone(), Status::Http(404)),
+        ];
+        let candidates = upgrade_candidates(&results);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates["http://a.com/x"], "https://a.com/x");
+    }
+
+    #[test]
+    fn rewrites_markdown_link_with_escapes() {
+        let path = file("escaped.md", "[a](http://a.com/old\\_x \"title\")\n");
+        let fixed = fix(&moved(&path, "http://a.com/new"), &HashMap::new());
+        let fixed = fixed[0].as_ref().unwrap();
+        assert_eq!(fixed.edits, 1);
+        assert!(fixed.skipped.is_empty());
+        assert_eq!(fixed.after, "[a](http://a.com/new \"title\")\n");
+    }
+
+    #[test]
+    fn html_links_keep_their_entities() {
+        let path = file(
+            "entities.html",
+            "<a href=\"http://a.com/?x=1&amp;y=2\">a</a>\n",
+        );
+        let fixed = fix(&moved(&path, "http://a.com/?z=1&w=2"), &HashMap::new());
+        assert_eq!(
+            fixed[0].as_ref().unwrap().after,
+            "<a href=\"http://a.com/?z=1&amp;w=2\">a</a>\n"
+        );
+    }
+
+    #[test]
+    fn upgrades_go_through_the_upgrade_map() {
+        let path = file("upgrade.md", "see http://a.com/page\n");
+        let content = fs::read_to_string(&path).unwrap();
+        let results: Vec<(Link, Status)> = find_links(&path, &content)
+            .into_iter()
+            .map(|link| (link, Status::Ok))
+            .collect();
+        let fixed = fix(&results, &upgrade_candidates(&results));
+        assert_eq!(fixed[0].as_ref().unwrap().after, "see https://a.com/page\n");
+    }
+
+    #[test]
+    fn file_changed_since_the_check_is_skipped() {
+        let path = file("changed.md", "[a](http://a.com/old)\n");
+        let results = moved(&path, "http://a.com/new");
+        fs::write(&path, "intro\n[a](http://a.com/old)\n").unwrap();
+        let fixed = fix(&results, &HashMap::new());
+        let fixed = fixed[0].as_ref().unwrap();
+        assert_eq!(fixed.edits, 0);
+        assert_eq!(fixed.skipped, vec!["http://a.com/old".to_string()]);
+        assert_eq!(fixed.after, fixed.before);
+    }
+
+    #[test]
+    fn diff_has_a_hunk_per_changed_line() {
+        let fixed = Fixed {
+            path: PathBuf::from("./docs/a.md"),
+            before: "one\ntwo\nthree\n".to_string(),
+            after: "one\n2\nthree\n".to_string(),
+            edits: 1,
+            skipped: Vec::new(),
+        };
+        assert_eq!(
+            fixed.diff(),
+            "--- a/docs/a.md\n+++ b/docs/a.md\n@@ -2 +2 @@\n-two\n+2\n"
+        );
+    }
+}
@@ -0,0 +1,700 @@
+use crate::local::is_local;
+use lazy_static::lazy_static;
+use linkify::{LinkFinder, LinkKind};
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+use regex::Regex;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+lazy_static! {
+    /// only urls, a bare email address isn't something that can be checked
+    static ref FINDER: LinkFinder = {
+        let mut finder = LinkFinder::new();
+        finder.kinds(&[LinkKind::Url]);
+        finder
+    };
+    /// `<!-- brokenlinks-ignore -->`, with -start and -end for whole sections
+    static ref IGNORE_MARKER: Regex =
+        Regex::new(r"<!--\s*brokenlinks-ignore(-start|-end)?\s*-->").unwrap();
+    /// parts of an html file that can't hold a real link: comments, and what's inside scripts,
+    /// styles and code. the tags themselves stay since a script can have a src
+    static ref HTML_SKIPPED: Regex = Regex::new(
+        r"(?is)<!--.*?-->|<script\b[^>]*>(.*?)</script\s*>|<style\b[^>]*>(.*?)</style\s*>|<pre\b[^>]*>(.*?)</pre\s*>|<code\b[^>]*>(.*?)</code\s*>"
+    )
+    .unwrap();
+    /// a tag with an href or src, the value in double, single or no quotes
+    static ref HTML_LINK: Regex = Regex::new(
+        r#"(?is)<([a-z][a-z0-9]*)\b[^>]*?\s(?:href|src)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))[^>]*>"#
+    )
+    .unwrap();
+    static ref HTML_OPEN_A: Regex = Regex::new(r"(?i)^<a[\s>]").unwrap();
+    static ref HTML_CLOSE_A: Regex = Regex::new(r"(?i)</a\s*>").unwrap();
+    /// a line with nothing but whitespace, which ends a markdown paragraph
+    static ref BLANK_LINE: Regex = Regex::new(r"\n[ \t]*\r?\n").unwrap();
+    static ref HTML_TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+    /// ``inline literals``, which show code and not links
+    static ref RST_LITERAL: Regex = Regex::new(r"``[^`]+``").unwrap();
+    /// `text <url>`_ and the anonymous `text <url>`__
+    static ref RST_EMBEDDED: Regex = Regex::new(r"`([^`<]*?)\s*<([^`>]+)>`__?").unwrap();
+    /// `.. _name: url`, which named references elsewhere in the file point at
+    static ref RST_TARGET: Regex =
+        Regex::new(r"(?m)^[ \t]*\.\.[ \t]+_([^:\n]*):[ \t]+(\S+)[ \t]*$").unwrap();
+    /// `.. image:: url` and `.. figure:: url`, also as a `|substitution|`
+    static ref RST_IMAGE: Regex =
+        Regex::new(r"(?m)^[ \t]*\.\.[ \t]+(?:\|[^|\n]+\|[ \t]+)?(?:image|figure)::[ \t]+(\S+)")
+            .unwrap();
+}
+
+/// a link and where it was found. line and column count from 1, the column in characters.
+/// offset is the byte in the file the url starts at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+    pub url: String,
+    /// the url the way it's written at offset, escapes and entities included. empty when
+    /// where it's written couldn't be told
+    pub raw: String,
+    /// written in html, so a url put in its place needs its entities encoded
+    pub html: bool,
+    /// what the reader sees for the link, when the format has such a thing
+    pub text: Option<String>,
+}
+
+/// how a file gets read for links, picked by its extension
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Syntax {
+    Markdown,
+    Html,
+    Rst,
+    /// anything else, where only urls written out in full count
+    Plain,
+}
+
+impl Syntax {
+    fn of(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        match extension.as_str() {
+            "md" | "markdown" | "mdown" | "mkd" | "mdx" => Syntax::Markdown,
+            "html" | "htm" | "xhtml" => Syntax::Html,
+            "rst" | "rest" => Syntax::Rst,
+            _ => Syntax::Plain,
+        }
+    }
+}
+
+/// a link as an extractor found it, before it gets a line and column. start..end is where
+/// the url is written
+struct Found {
+    start: usize,
+    end: usize,
+    url: String,
+    text: Option<String>,
+    html: bool,
+}
+
+/// the links in a file, read the way its format says so code blocks are passed over and link
+/// text is kept. links on lines marked to be ignored are left out, and so is anything that
+/// isn't a web link or a path into the tree, like mailto:
+pub fn find_links(path: &Path, content: &str) -> Vec<Link> {
+    let found = match Syntax::of(path) {
+        Syntax::Markdown => markdown(content),
+        Syntax::Html => html(content, 0),
+        Syntax::Rst => rst(content),
+        Syntax::Plain => plain(content, &[]),
+    };
+
+    let lines = LineIndex::new(content);
+    let ignored = ignored_lines(content);
+    let mut links: Vec<Link> = found
+        .into_iter()
+        .filter(|found| checkable(&found.url))
+        .map(|found| {
+            let (line, column) = lines.location(content, found.start);
+            Link {
+                path: path.to_path_buf(),
+                line,
+                column,
+                offset: found.start,
+                url: found.url,
+                raw: content[found.start..found.end].to_string(),
+                html: found.html,
+                text: found.text,
+            }
+        })
+        .filter(|link| !ignored.contains(&link.line))
+        .collect();
+    links.sort_by_key(|link| link.offset);
+    links.dedup_by_key(|link| link.offset);
+    links
+}
+
+/// http and https links, and links to files in the tree
+fn checkable(url: &str) -> bool {
+    let lower = url.to_lowercase();
+    if url.is_empty() {
+        false
+    } else if is_local(url) {
+        true
+    } else {
+        lower.starts_with("http://") || lower.starts_with("https://")
+    }
+}
+
+/// urls written out in text, skipping any that start inside one of the covered ranges
+fn plain(content: &str, covered: &[Range<usize>]) -> Vec<Found> {
+    FINDER
+        .links(content)
+        .filter(|link| !covered.iter().any(|range| range.contains(&link.start())))
+        .map(|link| Found {
+            start: link.start(),
+            end: link.end(),
+            url: link.as_str().to_string(),
+            text: None,
+            html: false,
+        })
+        .collect()
+}
+
+/// inline links and images, reference definitions, autolinks and html, plus bare urls the way
+/// github links them. nothing in code spans or code blocks counts
+fn markdown(content: &str) -> Vec<Found> {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+    let parser = Parser::new_ext(content, options);
+    let mut found = Vec::new();
+    // parts of the file already looked at, so bare url finding doesn't pick them up again
+    let mut covered: Vec<Range<usize>> = Vec::new();
+
+    // a definition is reported where its url is written rather than everywhere it's used
+    let mut definitions: Vec<(String, Found)> = Vec::new();
+    for (label, definition) in parser.reference_definitions().iter() {
+        let span = definition.span.clone();
+        let written = &content[span.clone()];
+        let after_label = written.find("]:").map_or(0, |i| i + 2);
+        let dest = destination(&written[after_label..]);
+        covered.push(span.clone());
+        definitions.push((
+            label.to_lowercase(),
+            Found {
+                start: span.start + after_label + dest.start,
+                end: span.start + after_label + dest.end,
+                url: definition.dest.to_string(),
+                text: None,
+                html: false,
+            },
+        ));
+    }
+
+    // links being read, innermost last, with their text so far. reference links have no
+    // Found of their own, just the label so their text can go on the definition
+    let mut open: Vec<(Option<Found>, Option<String>, String)> = Vec::new();
+    let mut reference_text: HashMap<String, String> = HashMap::new();
+    // end of the html block read last, the events inside it have been dealt with
+    let mut skip_until = 0;
+    let lower = content.to_ascii_lowercase();
+    for (event, range) in parser.into_offset_iter() {
+        if range.start < skip_until {
+            continue;
+        }
+        match event {
+            Event::Start(
+                Tag::Link {
+                    link_type,
+                    dest_url,
+                    id,
+                    ..
+                }
+                | Tag::Image {
+                    link_type,
+                    dest_url,
+                    id,
+                    ..
+                },
+            ) => {
+                covered.push(range.clone());
+                if matches!(
+                    link_type,
+                    LinkType::Reference | LinkType::Collapsed | LinkType::Shortcut
+                ) {
+                    open.push((None, Some(id.to_lowercase()), String::new()));
+                } else {
+                    let written = &content[range.clone()];
+                    let dest = written
+                        .rfind(dest_url.as_ref())
+                        .map(|i| i..i + dest_url.len())
+                        .or_else(|| {
+                            // escapes and entities make the url read differently from how
+                            // it's written, so it's the part between the ( and the title
+                            let open = written.rfind("](")? + 2;
+                            let dest = destination(written[open..].strip_suffix(')')?);
+                            Some(open + dest.start..open + dest.end)
+                        })
+                        .unwrap_or(0..0);
+                    let found = Found {
+                        start: range.start + dest.start,
+                        end: range.start + dest.end,
+                        url: dest_url.to_string(),
+                        text: None,
+                        html: false,
+                    };
+                    open.push((Some(found), None, String::new()));
+                }
+            }
+            Event::End(TagEnd::Link | TagEnd::Image) => {
+                let Some((link, label, text)) = open.pop() else {
+                    continue;
+                };
+                // an image inside a link gives the link its alt text
+                if let Some((_, _, outer)) = open.last_mut() {
+                    outer.push_str(&text);
+                }
+                let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                let text = (!text.is_empty()).then_some(text);
+                match (link, label) {
+                    (Some(mut link), _) => {
+                        link.text = text;
+                        found.push(link);
+                    }
+                    (None, Some(label)) => {
+                        if let Some(text) = text {
+                            reference_text.entry(label).or_insert(text);
+                        }
+                    }
+                    (None, None) => {}
+                }
+            }
+            Event::Text(text) => {
+                if let Some((_, _, link_text)) = open.last_mut() {
+                    link_text.push_str(&text);
+                }
+            }
+            Event::Code(text) => {
+                covered.push(range);
+                if let Some((_, _, link_text)) = open.last_mut() {
+                    link_text.push_str(&text);
+                }
+            }
+            Event::Start(Tag::CodeBlock(_)) => covered.push(range),
+            // the parser hands html blocks over a line at a time, so the whole block is read
+            // at its start instead. that keeps the text of an <a> and a <script> spread over
+            // several lines together
+            Event::Start(Tag::HtmlBlock) => {
+                let end = html_block_end(&lower, range.clone());
+                found.extend(html(&content[range.start..end], range.start));
+                covered.push(range.start..end);
+                skip_until = end;
+            }
+            Event::Html(_) => {
+                found.extend(html(&content[range.clone()], range.start));
+                covered.push(range);
+            }
+            // inline html comes a tag at a time too, an <a> is read along with its text
+            Event::InlineHtml(_) => {
+                let end = inline_anchor_end(&lower, range.clone()).unwrap_or(range.end);
+                found.extend(html(&content[range.start..end], range.start));
+                covered.push(range.start..end);
+                skip_until = end;
+            }
+            _ => {}
+        }
+    }
+
+    for (label, mut definition) in definitions {
+        definition.text = reference_text.remove(&label);
+        found.push(definition);
+    }
+    found.extend(plain(content, &covered));
+    found
+}
+
+/// where the url is in the destination of an inline link or a definition, which is whatever
+/// comes first after any whitespace. the <> it can be wrapped in are left out
+fn destination(written: &str) -> Range<usize> {
+    let start = written.len() - written.trim_start().len();
+    let dest = &written[start..];
+    match dest.strip_prefix('<') {
+        Some(inner) => start + 1..start + 1 + inner.find('>').unwrap_or(inner.len()),
+        None => start..start + dest.find(char::is_whitespace).unwrap_or(dest.len()),
+    }
+}
+
+/// where an html block really ends. a blank line ends a block in markdown, but a <script>,
+/// <style> or comment left open in it carries on to its closing tag. lower is the file in
+/// ascii lower case, which keeps the byte offsets the same
+fn html_block_end(lower: &str, block: Range<usize>) -> usize {
+    let inside = &lower[block.clone()];
+    for (open, close) in [
+        ("<script", "</script"),
+        ("<style", "</style"),
+        ("<!--", "-->"),
+    ] {
+        let unclosed = inside
+            .rfind(open)
+            .is_some_and(|start| !inside[start..].contains(close));
+        if unclosed {
+            if let Some(at) = lower[block.end..].find(close) {
+                let after = block.end + at;
+                return after + lower[after..].find('>').map_or(close.len(), |end| end + 1);
+            }
+        }
+    }
+    block.end
+}
+
+/// the end of the </a> that closes an inline <a> tag, when it's in the same paragraph. lower is
+/// the file in ascii lower case
+fn inline_anchor_end(lower: &str, tag: Range<usize>) -> Option<usize> {
+    let opened = &lower[tag.clone()];
+    if !HTML_OPEN_A.is_match(opened) || HTML_CLOSE_A.is_match(opened) {
+        return None;
+    }
+    let rest = &lower[tag.end..];
+    let paragraph = BLANK_LINE
+        .find(rest)
+        .map_or(rest, |blank| &rest[..blank.start()]);
+    HTML_CLOSE_A
+        .find(paragraph)
+        .map(|close| tag.end + close.end())
+}
+
+/// href and src attributes, with the text inside <a> tags. offset is where the html starts
+/// in the file, for html that's part of a markdown file
+fn html(content: &str, offset: usize) -> Vec<Found> {
+    // blank out the skipped parts with spaces so byte offsets stay the same
+    let mut visible = content.to_string();
+    for captures in HTML_SKIPPED.captures_iter(content) {
+        let skipped = captures
+            .iter()
+            .skip(1)
+            .flatten()
+            .next()
+            .or_else(|| captures.get(0))
+            .unwrap();
+        visible.replace_range(skipped.range(), &blank(skipped.as_str()));
+    }
+
+    HTML_LINK
+        .captures_iter(&visible)
+        .filter_map(|captures| {
+            let value = captures
+                .get(2)
+                .or_else(|| captures.get(3))
+                .or_else(|| captures.get(4))?;
+            let tag = captures.get(1)?.as_str();
+            let text = if tag.eq_ignore_ascii_case("a") {
+                let after = captures.get(0)?.end();
+                HTML_CLOSE_A.find(&visible[after..]).and_then(|close| {
+                    let inner = HTML_TAG.replace_all(&visible[after..after + close.start()], " ");
+                    let inner = decode_entities(&inner)
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    (!inner.is_empty()).then_some(inner)
+                })
+            } else {
+                None
+            };
+            let raw = value.as_str().trim_end();
+            let trimmed = raw.trim_start();
+            let start = offset + value.start() + raw.len() - trimmed.len();
+            Some(Found {
+                start,
+                end: start + trimmed.len(),
+                url: decode_entities(trimmed),
+                text,
+                html: true,
+            })
+        })
+        .collect()
+}
+
+/// the few entities that turn up in urls and link text
+fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// embedded `text <url>`_ links, link targets, image and figure directives and standalone
+/// urls. literal blocks, code directives, comments and ``literals`` are passed over
+fn rst(content: &str) -> Vec<Found> {
+    let visible = rst_visible(content);
+    let mut found = Vec::new();
+    let mut covered = Vec::new();
+
+    for captures in RST_EMBEDDED.captures_iter(&visible) {
+        covered.push(captures.get(0).unwrap().range());
+        let url = captures.get(2).unwrap();
+        // `text <name_>`_ points at a target, not a url
+        if url.as_str().ends_with('_') {
+            continue;
+        }
+        let text = captures.get(1).unwrap().as_str().trim();
+        found.push(Found {
+            start: url.start(),
+            end: url.end(),
+            url: url.as_str().to_string(),
+            text: (!text.is_empty()).then(|| text.to_string()),
+            html: false,
+        });
+    }
+    for captures in RST_TARGET.captures_iter(&visible) {
+        covered.push(captures.get(0).unwrap().range());
+        let url = captures.get(2).unwrap();
+        if url.as_str().ends_with('_') {
+            continue;
+        }
+        let name = captures.get(1).unwrap().as_str().trim().trim_matches('`');
+        found.push(Found {
+            start: url.start(),
+            end: url.end(),
+            url: url.as_str().to_string(),
+            text: (!name.is_empty() && name != "_").then(|| name.to_string()),
+            html: false,
+        });
+    }
+    for captures in RST_IMAGE.captures_iter(&visible) {
+        covered.push(captures.get(0).unwrap().range());
+        let url = captures.get(1).unwrap();
+        found.push(Found {
+            start: url.start(),
+            end: url.end(),
+            url: url.as_str().to_string(),
+            text: None,
+            html: false,
+        });
+    }
+    found.extend(plain(&visible, &covered));
+    found
+}
+
+/// the rst file with everything that isn't prose or a link blanked out by spaces, keeping the
+/// byte offsets of what's left
+fn rst_visible(content: &str) -> String {
+    let mut visible = String::with_capacity(content.len());
+    // indent of the line that started a literal block or comment, lines indented further in
+    // belong to it
+    let mut block: Option<usize> = None;
+    for line in content.split_inclusive('\n') {
+        let text = line.trim_end();
+        let indent = text.len() - text.trim_start().len();
+        let inside = match block {
+            Some(_) if text.is_empty() => true,
+            Some(start) if indent > start => true,
+            _ => {
+                block = None;
+                false
+            }
+        };
+        if inside {
+            visible.push_str(&blank(line));
+            continue;
+        }
+
+        let directive = text.trim_start();
+        let code_directive = ["code", "code-block", "sourcecode", "literalinclude"]
+            .iter()
+            .any(|name| directive.starts_with(&format!(".. {}::", name)));
+        // `.. ` followed by anything but a target, directive, substitution or footnote is a
+        // comment
+        let comment = directive.starts_with("..")
+            && !directive.starts_with(".. _")
+            && !directive.starts_with(".. |")
+            && !directive.starts_with(".. [")
+            && !directive.contains("::");
+        if code_directive || comment {
+            block = Some(indent);
+            visible.push_str(&blank(line));
+            continue;
+        }
+        // a paragraph ending in :: starts a literal block, directives end in :: too but their
+        // indented body is content
+        if text.ends_with("::") && !directive.starts_with("..") {
+            block = Some(indent);
+        }
+        visible.push_str(line);
+    }
+
+    let mut out = visible.clone();
+    for literal in RST_LITERAL.find_iter(&visible) {
+        out.replace_range(literal.range(), &blank(literal.as_str()));
+    }
+    out
+}
+
+/// spaces in place of every byte but the line ending
+fn blank(line: &str) -> String {
+    let text = line.trim_end_matches(['\r', '\n']);
+    " ".repeat(text.len()) + &line[text.len()..]
+}
+
+/// line numbers the ignore markers cover. a marker on a line of its own covers the line after
+/// it, otherwise the line it's on. everything from a -start marker to an -end marker is covered
+fn ignored_lines(content: &str) -> HashSet<usize> {
+    let mut ignored = HashSet::new();
+    let mut in_section = false;
+    let mut next_line = false;
+    for (i, line) in content.lines().enumerate() {
+        let number = i + 1;
+        if in_section || next_line {
+            ignored.insert(number);
+        }
+        next_line = false;
+        for marker in IGNORE_MARKER.captures_iter(line) {
+            match marker.get(1).map(|kind| kind.as_str()) {
+                Some("-start") => {
+                    in_section = true;
+                    ignored.insert(number);
+                }
+                Some(_) => in_section = false,
+                None if IGNORE_MARKER.replace_all(line, "").trim().is_empty() => next_line = true,
+                None => {
+                    ignored.insert(number);
+                }
+            }
+        }
+    }
+    ignored
+}
+
+/// byte offsets where each line starts, for turning an offset into a line and column
+pub struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(content: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        LineIndex { starts }
+    }
+
+    pub fn location(&self, content: &str, offset: usize) -> (usize, usize) {
+        let line = self.starts.partition_point(|&start| start <= offset);
+        let start = self.starts[line - 1];
+        let column = content[start..offset].chars().count() + 1;
+        (line, column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(name: &str, content: &str) -> Vec<(String, Option<String>, String)> {
+        find_links(Path::new(name), content)
+            .into_iter()
+            .map(|link| (link.url, link.text, link.raw))
+            .collect()
+    }
+
+    #[test]
+    fn ignore_markers() {
+        let text = "a <!-- brokenlinks-ignore -->\n<!-- brokenlinks-ignore -->\nb\nc\n<!-- brokenlinks-ignore-start -->\nd\n<!-- brokenlinks-ignore-end -->\ne\n";
+        let mut ignored: Vec<usize> = ignored_lines(text).into_iter().collect();
+        ignored.sort();
+        assert_eq!(ignored, [1, 3, 5, 6, 7]);
+    }
+
+    #[test]
+    fn markdown_skips_code_and_keeps_text() {
+        let found = links(
+            "a.md",
+            "[the *docs*](https://a.com/docs) `https://a.com/code`\n\n    https://a.com/block\n\nsee https://a.com/bare.\n",
+        );
+        assert_eq!(
+            found,
+            [
+                (
+                    "https://a.com/docs".to_string(),
+                    Some("the docs".to_string()),
+                    "https://a.com/docs".to_string()
+                ),
+                (
+                    "https://a.com/bare".to_string(),
+                    None,
+                    "https://a.com/bare".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn markdown_raw_destination_keeps_escapes() {
+        let found = links(
+            "a.md",
+            "[a](https://a.com/a\\_b&amp;c \"t\")\n\n[r]: <https://a.com/r x>\n\n[r]\n",
+        );
+        assert_eq!(found[0].0, "https://a.com/a_b&c");
+        assert_eq!(found[0].2, "https://a.com/a\\_b&amp;c");
+        assert_eq!(found[1].0, "https://a.com/r x");
+        assert_eq!(found[1].1.as_deref(), Some("r"));
+        assert_eq!(found[1].2, "https://a.com/r x");
+    }
+
+    #[test]
+    fn inline_anchor_keeps_its_text() {
+        let found = links(
+            "a.md",
+            "See <a href=\"https://a.com/x?a=1&amp;b=2\">the\nsite</a> now\n",
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, "https://a.com/x?a=1&b=2");
+        assert_eq!(found[0].1.as_deref(), Some("the site"));
+        assert_eq!(found[0].2, "https://a.com/x?a=1&amp;b=2");
+    }
+
+    #[test]
+    fn html_block_script_over_a_blank_line() {
+        let found = links(
+            "a.md",
+            "<div>\n<script>\nvar a = 1;\n\nfetch(\"https://a.com/script\");\n</script>\n</div>\n\n[after](https://a.com/after)\n",
+        );
+        let urls: Vec<&str> = found.iter().map(|(url, _, _)| url.as_str()).collect();
+        assert_eq!(urls, ["https://a.com/after"]);
+    }
+
+    #[test]
+    fn html_skips_comments_and_scripts() {
+        let found = links(
+            "a.html",
+            "<!-- <a href=\"https://a.com/comment\"> -->\n<script src=\"https://a.com/s.js\">var u = \"https://a.com/inline\";</script>\n<img src='https://a.com/i.png'>\n",
+        );
+        let urls: Vec<&str> = found.iter().map(|(url, _, _)| url.as_str()).collect();
+        assert_eq!(urls, ["https://a.com/s.js", "https://a.com/i.png"]);
+    }
+
+    #[test]
+    fn rst_links_and_literals() {
+        let found = links(
+            "a.rst",
+            "`Docs <https://a.com/docs>`_ and ``https://a.com/literal``\n\n.. _target: https://a.com/target\n\n::\n\n    https://a.com/block\n",
+        );
+        let urls: Vec<&str> = found.iter().map(|(url, _, _)| url.as_str()).collect();
+        assert_eq!(urls, ["https://a.com/docs", "https://a.com/target"]);
+        assert_eq!(found[0].1.as_deref(), Some("Docs"));
+    }
+
+    #[test]
+    fn line_and_column_count_characters() {
+        let found = find_links(Path::new("a.txt"), "x\nüü https://a.com\n");
+        assert_eq!((found[0].line, found[0].column), (2, 4));
+    }
+}
@@ -0,0 +1,274 @@
+use crate::checker::Status;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// true for links into the same tree, like `../guide.md`, `img/a.png` or `#usage`, as opposed
+/// to anything with a scheme such as `https:` or `mailto:`
+pub fn is_local(url: &str) -> bool {
+    if url.starts_with("//") {
+        return false;
+    }
+    // a scheme is letters, digits, + - and . before the first colon, and has to come before
+    // any slash, question mark or hash
+    match url.find(|c: char| !(c.is_ascii_alphanumeric() || "+-.".contains(c))) {
+        Some(end) => !(end > 0 && url[end..].starts_with(':')),
+        None => true,
+    }
+}
+
+/// checks links to files in the tree without touching the network. the headings of every
+/// markdown file an anchor points into are read once and kept
+pub struct LocalChecker {
+    /// absolute and normalized, so links can be told to stay inside it
+    root: PathBuf,
+    anchors: HashMap<PathBuf, HashSet<String>>,
+}
+
+impl LocalChecker {
+    /// root is the directory being checked, links starting with / are taken to be from there
+    pub fn new(root: &Path) -> Self {
+        LocalChecker {
+            root: normalize(&absolute(root)),
+            anchors: HashMap::new(),
+        }
+    }
+
+    /// from is the file the link is in. a link that leads out of the directory counts as
+    /// missing, even when there's a file where it points
+    pub fn check(&mut self, from: &Path, url: &str) -> Status {
+        let from = absolute(from);
+        let url = url.split_once('?').map_or(url, |(path, _)| path);
+        let (target, anchor) = match url.split_once('#') {
+            Some((target, anchor)) => (target, Some(anchor)),
+            None => (url, None),
+        };
+        let target = percent_decode(target);
+
+        let path = if target.is_empty() {
+            from.clone()
+        } else if let Some(absolute) = target.strip_prefix('/') {
+            self.root.join(absolute)
+        } else {
+            from.parent().unwrap_or(Path::new("")).join(&target)
+        };
+        let path = normalize(&path);
+        if !path.starts_with(&self.root) || !path.exists() {
+            return Status::MissingFile;
+        }
+
+        match anchor {
+            Some(anchor) if !anchor.is_empty() && is_markdown(&path) => {
+                let anchor = percent_decode(anchor).to_lowercase();
+                let anchors = self.anchors.entry(path.clone()).or_insert_with(|| {
+                    fs::read_to_string(&path).map_or_else(|_| HashSet::new(), |text| anchors(&text))
+                });
+                if anchors.contains(&anchor) {
+                    Status::Ok
+                } else {
+                    Status::MissingAnchor
+                }
+            }
+            _ => Status::Ok,
+        }
+    }
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| matches!(extension.to_lowercase().as_str(), "md" | "markdown"))
+}
+
+/// the path from the root of the file system, left as it is if the working directory is gone
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// takes out the `.` and `..` parts so the same file always ends up with the same path. symlinks
+/// aren't followed, a `..` just drops the name before it
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match out.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    out.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => out.push(".."),
+            },
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// %20 and friends back into the characters they stand for
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = |b: u8| (b as char).to_digit(16);
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(high), Some(low)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                out.push((high * 16 + low) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// every anchor a markdown file has, the way github names them: the heading text in lower
+/// case, punctuation dropped and spaces turned into dashes, with -1, -2 and so on added to
+/// repeats. html `id` and `name` attributes count too
+fn anchors(text: &str) -> HashSet<String> {
+    let mut found = HashSet::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut add_heading = |heading: &str, found: &mut HashSet<String>| {
+        let slug = slug(heading);
+        let count = seen.entry(slug.clone()).or_insert(0);
+        if *count == 0 {
+            found.insert(slug);
+        } else {
+            found.insert(format!("{}-{}", slug, count));
+        }
+        *count += 1;
+    };
+
+    let lines: Vec<&str> = text.lines().collect();
+    let mut fence: Option<&str> = None;
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        // nothing inside a fenced code block is a heading
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+
+        let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with([' ', '\t']) {
+            let heading = trimmed[hashes..].trim().trim_end_matches('#').trim_end();
+            add_heading(heading, &mut found);
+        } else if !trimmed.is_empty() {
+            // a line underlined with === or --- is a heading too
+            let underline = lines.get(i + 1).map_or("", |next| next.trim());
+            if !underline.is_empty()
+                && (underline.chars().all(|c| c == '=') || underline.chars().all(|c| c == '-'))
+            {
+                add_heading(trimmed.trim_end(), &mut found);
+            }
+        }
+
+        for attribute in ["id=\"", "name=\""] {
+            for (start, _) in line.match_indices(attribute) {
+                let value = &line[start + attribute.len()..];
+                if let Some(end) = value.find('"') {
+                    found.insert(value[..end].to_lowercase());
+                }
+            }
+        }
+    }
+    found
+}
+
+fn slug(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_links_have_no_scheme() {
+        for url in [
+            "guide.md",
+            "../a/b.png",
+            "#usage",
+            "/docs/x.md",
+            "a/b:c.md",
+            "file.md?x=1",
+        ] {
+            assert!(is_local(url), "{}", url);
+        }
+        for url in [
+            "https://a.com",
+            "mailto:me@a.com",
+            "//cdn.a.com/x.js",
+            "ftp:x",
+        ] {
+            assert!(!is_local(url), "{}", url);
+        }
+    }
+
+    #[test]
+    fn slugs_like_github() {
+        assert_eq!(slug("Getting Started"), "getting-started");
+        assert_eq!(slug("What's new in v2.0?"), "whats-new-in-v20");
+        assert_eq!(slug("snake_case and-dashes"), "snake_case-and-dashes");
+        assert_eq!(slug("Ünïcode Títle"), "ünïcode-títle");
+    }
+
+    #[test]
+    fn anchors_number_repeats_and_skip_code() {
+        let text = "# Intro\n## Intro\nSetup\n=====\n```\n# not a heading\n```\n<a id=\"Custom\"></a>\n#nospace\n";
+        let found = anchors(text);
+        let mut found: Vec<&str> = found.iter().map(String::as_str).collect();
+        found.sort();
+        assert_eq!(found, ["custom", "intro", "intro-1", "setup"]);
+    }
+
+    #[test]
+    fn normalize_keeps_leading_parents() {
+        assert_eq!(normalize(Path::new("a/./b/../c")), Path::new("a/c"));
+        assert_eq!(normalize(Path::new("../a/../../b")), Path::new("../../b"));
+        assert_eq!(normalize(Path::new("/a/../../b")), Path::new("/b"));
+    }
+
+    #[test]
+    fn links_out_of_the_directory_are_missing() {
+        let dir = std::env::temp_dir().join(format!("brokenlinks-local-{}", std::process::id()));
+        let root = dir.join("docs");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(dir.join("outside.md"), "# Outside\n").unwrap();
+        fs::write(root.join("sub/in.md"), "# Inside\n").unwrap();
+        let from = root.join("a.md");
+        fs::write(&from, "").unwrap();
+
+        let mut checker = LocalChecker::new(&root);
+        assert_eq!(checker.check(&from, "sub/in.md"), Status::Ok);
+        assert_eq!(checker.check(&from, "sub/in.md#inside"), Status::Ok);
+        assert_eq!(checker.check(&from, "/sub/in.md"), Status::Ok);
+        assert_eq!(
+            checker.check(&from, "sub/in.md#nope"),
+            Status::MissingAnchor
+        );
+        assert_eq!(checker.check(&from, "sub/gone.md"), Status::MissingFile);
+        assert_eq!(checker.check(&from, "../outside.md"), Status::MissingFile);
+        assert_eq!(checker.check(&from, "/../outside.md"), Status::MissingFile);
+    }
+}
@@ -1,14 +1,25 @@
-use clap::error::Result;
+mod cache;
+mod checker;
+mod fix;
+mod links;
+mod local;
+mod report;
+mod rules;
+mod walk;
+
+use cache::Cache;
+use checker::{Checker, Policy, Status, MAX_RETRY_WAIT};
 use clap::Parser;
-use lazy_static::lazy_static;
-use linkify::{LinkFinder, Links};
+use links::{find_links, Link};
+use local::{is_local, LocalChecker};
+use report::Format;
+use rules::{Config, Rules};
 use std::{
-    fs::{self, read_to_string},
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
+    time::Duration,
 };
-lazy_static! {
-    static ref FINDER: LinkFinder = LinkFinder::new();
-}
+use walk::{walk_files, WalkOptions};
 
 #[derive(Parser, Debug)]
 #[command(version, about = "Checks for broken links in a directory.", long_about = None)]
@@ -17,58 +28,279 @@ struct Args {
     /// Give a folder with broken links path
     #[arg(short, long)]
     broken_link_path: Option<PathBuf>,
+
+    /// How many links to check at the same time
+    #[arg(short, long, default_value_t = 8)]
+    jobs: usize,
+
+    /// Most requests a second sent to any one host, 0 for no limit
+    #[arg(long, default_value_t = 5.0)]
+    rate_limit: f64,
+
+    /// Seconds to wait for a connection before giving up on a link
+    #[arg(long, default_value_t = 10)]
+    connect_timeout: u64,
+
+    /// Seconds a whole request can take, connecting included, before giving up on a link
+    #[arg(long, default_value_t = 30)]
+    timeout: u64,
+
+    /// How many more times to try a link that timed out or got a 429 or 5xx
+    #[arg(long, default_value_t = 2)]
+    retries: u32,
+
+    /// Milliseconds to wait before the first retry, doubled for every retry after it
+    #[arg(long, default_value_t = 1000)]
+    retry_delay: u64,
+
+    /// File to remember working links in, so later runs can skip them
+    #[arg(long)]
+    cache: Option<PathBuf>,
+
+    /// Seconds a link in the cache counts as working before it gets checked again
+    #[arg(long, default_value_t = 24 * 60 * 60)]
+    cache_ttl: u64,
+
+    /// Config file with ignore rules, defaults to .brokenlinks.toml in the directory checked
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Don't check links matching this regex, can be given more than once
+    #[arg(long, value_name = "REGEX")]
+    exclude: Vec<String>,
+
+    /// Don't check links to this domain or its subdomains, can be given more than once
+    #[arg(long, value_name = "DOMAIN")]
+    exclude_domain: Vec<String>,
+
+    /// Don't read files matching this glob, relative to the directory checked
+    #[arg(long, value_name = "GLOB")]
+    exclude_path: Vec<String>,
+
+    /// Only read files matching this glob, relative to the directory checked
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Only read files with these extensions, like md,html
+    #[arg(long = "ext", value_name = "EXTENSIONS", value_delimiter = ',')]
+    extensions: Vec<String>,
+
+    /// Follow symlinks to files and directories instead of skipping them
+    #[arg(long)]
+    follow_symlinks: bool,
+
+    /// Read files even when .gitignore or .ignore leaves them out
+    #[arg(long)]
+    no_ignore: bool,
+
+    /// Count these status codes as working for a domain, like github.com=429,403
+    #[arg(long, value_name = "DOMAIN=CODES", value_parser = rules::parse_accept)]
+    accept: Vec<(String, Vec<u16>)>,
+
+    /// Rewrite links that moved permanently to where they went, and http links to https
+    /// when that works too
+    #[arg(long)]
+    fix: bool,
+
+    /// With --fix, print the changes as a diff and leave the files. The report goes to stderr
+    /// instead
+    #[arg(long, requires = "fix")]
+    dry_run: bool,
+
+    /// How to print the results
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
 }
 
-/// takes a direcotry and opens it converts to content in the files and runs a method
-fn directory_to_file_action<F>(files: &Path, method: &F) -> Result<()>
-where
-    F: Fn(String),
-{
-    let files = fs::read_dir(files)?;
-    for file in files {
-        let file = file.unwrap().path();
-        match read_to_string(&file) {
-            Ok(content) => {
-                method(content);
-            }
-            Err(_) => {
-                let _ = directory_to_file_action(&file, method);
+/// takes the links and sees if they actualy go anywhere, giving back every link with its
+/// status in the order they were found. each url is only checked once however many times it
+/// shows up, and not at all if the cache saw it working recently. links to files in the tree
+/// are looked up on disk instead
+fn check_links(
+    links: Vec<Link>,
+    checker: &Checker,
+    local: &mut LocalChecker,
+    rules: &Rules,
+    mut cache: Option<&mut Cache>,
+) -> Vec<(Link, Status)> {
+    let mut statuses: HashMap<String, Status> = HashMap::new();
+    let mut queued: HashSet<&str> = HashSet::new();
+    let mut unique: Vec<String> = Vec::new();
+    for link in &links {
+        let url = link.url.as_str();
+        if is_local(url) {
+            continue;
+        }
+        if cache.as_ref().is_some_and(|cache| cache.is_fresh(url)) {
+            statuses.insert(link.url.clone(), Status::Ok);
+        } else if queued.insert(url) {
+            unique.push(link.url.clone());
+        }
+    }
+
+    for (link, status) in unique.iter().zip(checker.check_all(&unique)) {
+        let status = rules.accept(link, status);
+        if let Some(cache) = cache.as_deref_mut() {
+            // a redirect isn't cached so it keeps getting reported until it's fixed
+            if status == Status::Ok {
+                cache.record(link);
             }
         }
+        statuses.insert(link.clone(), status);
     }
-    Ok(())
-}
 
-/// find the links through the linkify crate
-fn find_links(content: String) {
-    let links = FINDER.links(&content);
-    print_brokenlinks(links)
+    links
+        .into_iter()
+        .map(|link| {
+            // the same relative link means a different file depending on where it is
+            let status = if is_local(&link.url) {
+                local.check(&link.path, &link.url)
+            } else {
+                statuses[&link.url].clone()
+            };
+            (link, status)
+        })
+        .collect()
 }
 
-/// takes the links and sees if they actualy go anywhere
-fn print_brokenlinks(links: Links) {
-    for link in links {
-        let link = link.as_str();
-        match reqwest::blocking::get(link) {
-            Ok(result) => {
-                if result.status().is_success() {
-                    // if finds url and not 404 anything in the 200s
-                } else {
-                    // if its a 404 or anything else
-                    println!("{}\n", link);
-                }
+/// rewrites the links that can be fixed, or prints a diff of it for a dry run. http links are
+/// only upgraded once the https version has been checked and works
+fn fix_links(results: &[(Link, Status)], checker: &Checker, rules: &Rules, dry_run: bool) {
+    let candidates = fix::upgrade_candidates(results);
+    let secure: Vec<String> = candidates
+        .values()
+        .cloned()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let working: HashSet<&String> = secure
+        .iter()
+        .zip(checker.check_all(&secure))
+        .filter_map(|(url, status)| (rules.accept(url, status) == Status::Ok).then_some(url))
+        .collect();
+    let upgrades = candidates
+        .iter()
+        .filter(|(_, secure)| working.contains(secure))
+        .map(|(url, secure)| (url.clone(), secure.clone()))
+        .collect();
+
+    for fixed in fix::fix(results, &upgrades) {
+        if let Ok(fixed) = &fixed {
+            for url in &fixed.skipped {
+                eprintln!(
+                    "couldn't fix {} in {}, it isn't written there the way it was found",
+                    url,
+                    fixed.path.display()
+                );
             }
-            Err(_e) => {
-                println!("{}\n", link);
+        }
+        match fixed {
+            Ok(fixed) if fixed.edits == 0 => {}
+            Ok(fixed) if dry_run => print!("{}", fixed.diff()),
+            Ok(fixed) => match fixed.write() {
+                Ok(()) => eprintln!("fixed {} links in {}", fixed.edits, fixed.path.display()),
+                Err(e) => eprintln!("couldn't write {}: {}", fixed.path.display(), e),
+            },
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
+
+/// the config file with the command line rules added on top
+fn load_rules(cli: &Args, root: &Path) -> Result<Rules, rules::RuleError> {
+    let mut config = match &cli.config {
+        Some(path) => Config::load(path)?,
+        None => {
+            let path = root.join(rules::CONFIG_FILE);
+            if path.is_file() {
+                Config::load(&path)?
+            } else {
+                Config::default()
             }
         }
+    };
+    config.exclude.extend(cli.exclude.iter().cloned());
+    config
+        .exclude_domains
+        .extend(cli.exclude_domain.iter().cloned());
+    config
+        .exclude_paths
+        .extend(cli.exclude_path.iter().cloned());
+    for (domain, codes) in &cli.accept {
+        config
+            .accept
+            .entry(domain.clone())
+            .or_default()
+            .extend(codes);
     }
+    Rules::new(config)
 }
 
 /// main method
 fn main() {
     let cli = Args::parse();
     if let Some(path) = cli.broken_link_path.as_deref() {
-        let _ = directory_to_file_action(path, &find_links);
+        let rules = load_rules(&cli, path).unwrap_or_else(|e| {
+            eprintln!("bad ignore rule {}", e);
+            std::process::exit(2);
+        });
+        let options = WalkOptions {
+            include: cli.include.clone(),
+            extensions: cli.extensions.clone(),
+            follow_symlinks: cli.follow_symlinks,
+            no_ignore: cli.no_ignore,
+        };
+        let mut links = Vec::new();
+        let walked = walk_files(
+            path,
+            &options,
+            &rules,
+            &mut |file: &Path, content: String| links.extend(find_links(file, &content)),
+        );
+        match walked {
+            Ok(unreadable) => {
+                for error in unreadable {
+                    eprintln!("{}", error);
+                }
+            }
+            Err(e) => {
+                eprintln!("bad --include glob {}", e);
+                std::process::exit(2);
+            }
+        }
+        links.retain(|link| !rules.skips_link(&link.url));
+        let policy = Policy {
+            connect_timeout: Duration::from_secs(cli.connect_timeout),
+            timeout: Duration::from_secs(cli.timeout),
+            retries: cli.retries,
+            backoff: Duration::from_millis(cli.retry_delay),
+            max_wait: MAX_RETRY_WAIT,
+        };
+        let checker = Checker::new(cli.jobs, cli.rate_limit, policy);
+        let mut cache = cli.cache.as_deref().and_then(|path| {
+            Cache::load(path, Duration::from_secs(cli.cache_ttl))
+                .map_err(|e| eprintln!("couldn't read cache {}: {}", path.display(), e))
+                .ok()
+        });
+        let mut local = LocalChecker::new(path);
+        let results = check_links(links, &checker, &mut local, &rules, cache.as_mut());
+        if let Some(cache) = &cache {
+            if let Err(e) = cache.save() {
+                eprintln!("couldn't write cache: {}", e);
+            }
+        }
+        if cli.fix {
+            fix_links(&results, &checker, &rules, cli.dry_run);
+        }
+        // a dry run keeps stdout for the diff
+        if cli.dry_run {
+            eprint!("{}", report::render(cli.format, &results));
+        } else {
+            print!("{}", report::render(cli.format, &results));
+        }
+        // ci jobs go red when anything is broken
+        if results.iter().any(|(_, status)| status.is_broken()) {
+            std::process::exit(1);
+        }
     }
 }
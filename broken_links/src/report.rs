@@ -0,0 +1,211 @@
+use crate::{checker::Status, links::Link};
+use serde::Serialize;
+use serde_json::json;
+use std::fmt::Write;
+
+/// how the results get printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// path:line:col lines for people and editors
+    Text,
+    /// one json array of broken links
+    Json,
+    /// one json object per broken link per line
+    Jsonl,
+    /// junit xml with a test case for every link, for ci test report tabs
+    Junit,
+    /// sarif 2.1.0, for code scanning tools
+    Sarif,
+}
+
+/// a broken link or a warning as it shows up in the json reports
+#[derive(Serialize)]
+struct Finding<'a> {
+    level: &'static str,
+    path: String,
+    line: usize,
+    column: usize,
+    url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<&'a str>,
+    status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redirect: Option<&'a str>,
+    category: &'static str,
+    message: String,
+}
+
+impl<'a> Finding<'a> {
+    fn new(link: &'a Link, status: &'a Status) -> Self {
+        Finding {
+            level: level(status),
+            path: link.path.display().to_string(),
+            line: link.line,
+            column: link.column,
+            url: &link.url,
+            text: link.text.as_deref(),
+            status: status.code(),
+            redirect: match status {
+                Status::Redirected(to) => Some(to),
+                _ => None,
+            },
+            category: status.category(),
+            message: status.to_string(),
+        }
+    }
+}
+
+/// error for broken links, warning for ones that work but could do with fixing
+fn level(status: &Status) -> &'static str {
+    if status.is_warning() {
+        "warning"
+    } else {
+        "error"
+    }
+}
+
+/// every link that was checked with what came back, in the order they were found. broken
+/// links and warnings are reported, junit lists every link
+pub fn render(format: Format, results: &[(Link, Status)]) -> String {
+    let reported = results
+        .iter()
+        .filter(|(_, status)| status.is_broken() || status.is_warning());
+    match format {
+        Format::Text => reported
+            .map(|(link, status)| {
+                // path:line:col first so editors and terminals can jump straight to the link
+                format!(
+                    "{}:{}:{}: {}{} ({})\n",
+                    link.path.display(),
+                    link.line,
+                    link.column,
+                    if status.is_warning() { "warning: " } else { "" },
+                    link.url,
+                    status
+                )
+            })
+            .collect(),
+        Format::Json => {
+            let findings: Vec<Finding> = reported
+                .map(|(link, status)| Finding::new(link, status))
+                .collect();
+            serde_json::to_string_pretty(&findings).unwrap() + "\n"
+        }
+        Format::Jsonl => reported
+            .map(|(link, status)| {
+                serde_json::to_string(&Finding::new(link, status)).unwrap() + "\n"
+            })
+            .collect(),
+        Format::Junit => junit(results),
+        Format::Sarif => sarif(results),
+    }
+}
+
+fn junit(results: &[(Link, Status)]) -> String {
+    let failures = results
+        .iter()
+        .filter(|(_, status)| status.is_broken())
+        .count();
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<testsuites name=\"broken_links\" tests=\"{}\" failures=\"{}\">",
+        results.len(),
+        failures
+    );
+    let _ = writeln!(
+        out,
+        "  <testsuite name=\"broken_links\" tests=\"{}\" failures=\"{}\">",
+        results.len(),
+        failures
+    );
+    for (link, status) in results {
+        let location = format!("{}:{}:{}", link.path.display(), link.line, link.column);
+        let _ = write!(
+            out,
+            "    <testcase classname=\"{}\" name=\"{}\" file=\"{}\" line=\"{}\"",
+            xml_escape(&link.path.display().to_string()),
+            xml_escape(&link.url),
+            xml_escape(&link.path.display().to_string()),
+            link.line
+        );
+        if status.is_broken() {
+            out.push_str(">\n");
+            let _ = writeln!(
+                out,
+                "      <failure type=\"{}\" message=\"{}\">{}: {}</failure>",
+                status.category(),
+                xml_escape(&status.to_string()),
+                xml_escape(&location),
+                xml_escape(&link.url)
+            );
+            out.push_str("    </testcase>\n");
+        } else {
+            out.push_str("/>\n");
+        }
+    }
+    out.push_str("  </testsuite>\n</testsuites>\n");
+    out
+}
+
+fn sarif(results: &[(Link, Status)]) -> String {
+    let reported: Vec<&(Link, Status)> = results
+        .iter()
+        .filter(|(_, status)| status.is_broken() || status.is_warning())
+        .collect();
+    let mut rules: Vec<&'static str> = reported
+        .iter()
+        .map(|(_, status)| status.category())
+        .collect();
+    rules.sort_unstable();
+    rules.dedup();
+
+    let results: Vec<serde_json::Value> = reported
+        .iter()
+        .map(|(link, status)| {
+            json!({
+                "ruleId": status.category(),
+                "level": level(status),
+                "message": { "text": format!("{} ({})", link.url, status) },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": sarif_uri(link) },
+                        "region": { "startLine": link.line, "startColumn": link.column }
+                    }
+                }],
+                "properties": { "url": link.url, "text": link.text, "status": status.code() }
+            })
+        })
+        .collect();
+    let report = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "broken_links",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules
+                        .iter()
+                        .map(|rule| json!({ "id": rule, "name": rule }))
+                        .collect::<Vec<_>>()
+                }
+            },
+            "results": results
+        }]
+    });
+    serde_json::to_string_pretty(&report).unwrap() + "\n"
+}
+
+/// sarif wants forward slashes even on windows
+fn sarif_uri(link: &Link) -> String {
+    link.path.display().to_string().replace('\\', "/")
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
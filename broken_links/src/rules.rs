@@ -0,0 +1,202 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::Regex;
+use serde::Deserialize;
+use std::{collections::HashMap, fmt, fs, path::Path};
+use url::Url;
+
+use crate::checker::Status;
+
+/// name of the config file looked for in the directory being checked
+pub const CONFIG_FILE: &str = ".brokenlinks.toml";
+
+/// what goes in `.brokenlinks.toml`, everything is optional
+///
+/// ```toml
+/// exclude = ["^https?://localhost"]
+/// exclude_domains = ["example.com"]
+/// exclude_paths = ["CHANGELOG.md", "vendor/**"]
+///
+/// [accept]
+/// "linkedin.com" = [999]
+/// "github.com" = [429]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// regexes, a link matching any of them isn't checked
+    pub exclude: Vec<String>,
+    /// links to these hosts and their subdomains aren't checked
+    pub exclude_domains: Vec<String>,
+    /// globs relative to the directory being checked, matching files aren't read at all
+    pub exclude_paths: Vec<String>,
+    /// status codes that count as working for a host and its subdomains
+    pub accept: HashMap<String, Vec<u16>>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, RuleError> {
+        let text = fs::read_to_string(path).map_err(|e| RuleError::new(path.display(), e))?;
+        toml::from_str(&text).map_err(|e| RuleError::new(path.display(), e))
+    }
+}
+
+/// the config turned into something that can be matched against
+pub struct Rules {
+    exclude: Vec<Regex>,
+    exclude_domains: Vec<String>,
+    exclude_paths: GlobSet,
+    accept: HashMap<String, Vec<u16>>,
+}
+
+impl Rules {
+    pub fn new(config: Config) -> Result<Self, RuleError> {
+        let exclude = config
+            .exclude
+            .iter()
+            .map(|pattern| Regex::new(pattern).map_err(|e| RuleError::new(pattern, e)))
+            .collect::<Result<_, _>>()?;
+        let mut paths = GlobSetBuilder::new();
+        for pattern in &config.exclude_paths {
+            paths.add(Glob::new(pattern).map_err(|e| RuleError::new(pattern, e))?);
+        }
+        let exclude_paths = paths
+            .build()
+            .map_err(|e| RuleError::new("exclude_paths", e))?;
+        Ok(Rules {
+            exclude,
+            exclude_domains: config.exclude_domains.iter().map(|d| domain(d)).collect(),
+            exclude_paths,
+            accept: config
+                .accept
+                .into_iter()
+                .map(|(host, codes)| (domain(&host), codes))
+                .collect(),
+        })
+    }
+
+    /// true for a file that shouldn't be read, path is relative to the directory being checked
+    pub fn skips_file(&self, path: &Path) -> bool {
+        self.exclude_paths.is_match(path)
+    }
+
+    /// true for a link that shouldn't be checked or reported
+    pub fn skips_link(&self, url: &str) -> bool {
+        self.exclude.iter().any(|pattern| pattern.is_match(url))
+            || host(url).is_some_and(|host| {
+                self.exclude_domains
+                    .iter()
+                    .any(|excluded| within(&host, excluded))
+            })
+    }
+
+    /// turns an http status the config accepts for the link's host into a working link
+    pub fn accept(&self, url: &str, status: Status) -> Status {
+        let Status::Http(code) = status else {
+            return status;
+        };
+        let accepted = host(url).is_some_and(|host| {
+            self.accept
+                .iter()
+                .any(|(domain, codes)| within(&host, domain) && codes.contains(&code))
+        });
+        if accepted {
+            Status::Ok
+        } else {
+            status
+        }
+    }
+}
+
+/// `DOMAIN=CODE,CODE` as given to --accept
+pub fn parse_accept(arg: &str) -> Result<(String, Vec<u16>), String> {
+    let (domain, codes) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected DOMAIN=CODES, got `{}`", arg))?;
+    let codes = codes
+        .split(',')
+        .map(|code| {
+            code.trim()
+                .parse()
+                .map_err(|_| format!("`{}` isn't a status code", code))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((domain.to_string(), codes))
+}
+
+/// a rule from the config or the command line that couldn't be used, and where it came from
+#[derive(Debug)]
+pub struct RuleError {
+    source: String,
+    message: String,
+}
+
+impl RuleError {
+    fn new(source: impl fmt::Display, message: impl fmt::Display) -> Self {
+        RuleError {
+            source: source.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.source, self.message)
+    }
+}
+
+fn host(url: &str) -> Option<String> {
+    Url::parse(url).ok()?.host_str().map(domain)
+}
+
+/// lower case without a leading www. so `www.Example.com` and `example.com` are the same
+fn domain(host: &str) -> String {
+    let host = host.trim().to_lowercase();
+    host.strip_prefix("www.")
+        .map_or(host.clone(), str::to_string)
+}
+
+/// the domain itself or any subdomain of it
+fn within(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|rest| rest.ends_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_flag() {
+        assert_eq!(
+            parse_accept("github.com=429, 403").unwrap(),
+            ("github.com".to_string(), vec![429, 403])
+        );
+        assert!(parse_accept("github.com").is_err());
+        assert!(parse_accept("github.com=abc").is_err());
+    }
+
+    #[test]
+    fn domains_cover_subdomains_and_www() {
+        let rules = Rules::new(Config {
+            exclude: vec!["^https?://localhost".to_string()],
+            exclude_domains: vec!["www.Example.com".to_string()],
+            accept: HashMap::from([("linkedin.com".to_string(), vec![999])]),
+            ..Config::default()
+        })
+        .unwrap();
+        assert!(rules.skips_link("http://localhost:8080/x"));
+        assert!(rules.skips_link("https://docs.example.com/a"));
+        assert!(!rules.skips_link("https://notexample.com/a"));
+        assert_eq!(
+            rules.accept("https://www.linkedin.com/in/x", Status::Http(999)),
+            Status::Ok
+        );
+        assert_eq!(
+            rules.accept("https://linkedin.com/in/x", Status::Http(404)),
+            Status::Http(404)
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use crate::rules::Rules;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use std::{fs, io::ErrorKind, path::Path};
+
+/// which files under the directory get read
+pub struct WalkOptions {
+    /// globs relative to the directory, when there are any a file has to match one
+    pub include: Vec<String>,
+    /// extensions without the dot, when there are any a file has to have one of them
+    pub extensions: Vec<String>,
+    pub follow_symlinks: bool,
+    /// read files even when a .gitignore or .ignore says to leave them out
+    pub no_ignore: bool,
+}
+
+/// runs method with the path and content of every text file under root, in name order so the
+/// output is the same every run. .git, anything .gitignore or .ignore leaves out and files the
+/// rules exclude are skipped, and binary files are passed over quietly. files that can't be read
+/// for any other reason are given back as messages
+pub fn walk_files<F>(
+    root: &Path,
+    options: &WalkOptions,
+    rules: &Rules,
+    method: &mut F,
+) -> Result<Vec<String>, globset::Error>
+where
+    F: FnMut(&Path, String),
+{
+    let include = glob_set(&options.include)?;
+
+    let walker = WalkBuilder::new(root)
+        .hidden(false)
+        .follow_links(options.follow_symlinks)
+        .git_ignore(!options.no_ignore)
+        .git_global(!options.no_ignore)
+        .git_exclude(!options.no_ignore)
+        .ignore(!options.no_ignore)
+        .parents(!options.no_ignore)
+        // a docs folder that isn't its own repo should still get its .gitignore read
+        .require_git(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    let mut unreadable = Vec::new();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                unreadable.push(e.to_string());
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+            continue;
+        }
+        let path = entry.path();
+        let name = path.strip_prefix(root).unwrap_or(path);
+        if rules.skips_file(name)
+            || !(options.include.is_empty() || include.is_match(name))
+            || !(options.extensions.is_empty() || has_extension(path, &options.extensions))
+        {
+            continue;
+        }
+        match fs::read_to_string(path) {
+            Ok(content) => method(path, content),
+            // not utf-8, so an image or some other binary without links to check
+            Err(e) if e.kind() == ErrorKind::InvalidData => {}
+            Err(e) => unreadable.push(format!("couldn't read {}: {}", path.display(), e)),
+        }
+    }
+    Ok(unreadable)
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut set = GlobSetBuilder::new();
+    for pattern in patterns {
+        set.add(Glob::new(pattern)?);
+    }
+    set.build()
+}
+
+fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            extensions.iter().any(|wanted| {
+                wanted
+                    .trim_start_matches('.')
+                    .eq_ignore_ascii_case(extension)
+            })
+        })
+}
@@ -0,0 +1,162 @@
+use crate::data::{Graph, LinkNode};
+
+// Everything that has to happen to a freshly loaded graph before it can be laid out and drawn.
+// Links keep the direction they were written in, the incoming side is filled in as backlinks
+pub fn prepare(graph: Graph) -> Graph {
+    let mut graph = fix_graph(graph);
+    build_backlinks(&mut graph);
+    date_nodes(&mut graph);
+    label_clusters(&mut graph);
+    label_subgraphs(&mut graph);
+    graph
+}
+
+pub fn fix_graph(mut graph: Vec<LinkNode>) -> Vec<LinkNode> {
+    graph.sort_by_key(|node| node.id);
+    graph
+}
+
+pub fn build_backlinks(graph: &mut Graph) {
+    for node in graph.iter_mut() {
+        node.backlinks.clear();
+    }
+    for from in 0..graph.len() {
+        for i in 0..graph[from].links.len() {
+            let to = graph[from].links[i];
+            if to < graph.len() && !graph[to].backlinks.contains(&from) {
+                graph[to].backlinks.push(from);
+            }
+        }
+    }
+}
+
+// Sets when each node shows up in the timeline. That's when it was made if the graph has
+// creation times, otherwise when it was last modified, which is all Lockbook keeps. Needs the
+// backlinks
+pub fn date_nodes(graph: &mut Graph) {
+    let by_creation = has_creation_times(graph);
+    let date = |node: &LinkNode| {
+        if by_creation {
+            node.created
+        } else {
+            node.modified
+        }
+    };
+    for id in 0..graph.len() {
+        let node = &graph[id];
+        let appeared = date(node).or_else(|| {
+            node.backlinks
+                .iter()
+                .filter_map(|&from| date(&graph[from]))
+                .min()
+        });
+        graph[id].appeared = appeared;
+    }
+}
+
+pub fn has_creation_times(graph: &Graph) -> bool {
+    graph.iter().any(|node| node.created.is_some())
+}
+
+// True when from links to `to` but not the other way around
+pub fn is_one_way(graph: &Graph, from: usize, to: usize) -> bool {
+    graph[from].links.contains(&to) && !graph[to].links.contains(&from)
+}
+
+// Neighbour list of every node. Directed only follows links the way they were written,
+// undirected treats every link as going both ways
+pub fn adjacency(graph: &Graph, directed: bool) -> Vec<Vec<usize>> {
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); graph.len()];
+    for node in graph {
+        for &link in node.links.iter().filter(|&&link| link < graph.len()) {
+            if !adjacency[node.id].contains(&link) {
+                adjacency[node.id].push(link);
+            }
+            if !directed && !adjacency[link].contains(&node.id) {
+                adjacency[link].push(node.id);
+            }
+        }
+    }
+    adjacency
+}
+
+pub fn label_subgraphs(graph: &mut Graph) {
+    let mut bluecol = 1.0;
+    let mut redcol = 0.1;
+    let mut greencol = 0.5;
+
+    for i in 0..graph.len() {
+        if graph[i].color[2] == 0.0 {
+            if graph[i].degree() == 0 {
+                graph[i].color = [1.0, 1.0, 1.0];
+            } else {
+                dfs(graph, i, bluecol, redcol, greencol);
+
+                bluecol = (bluecol * 0.7 + 0.2) % 1.0;
+                redcol = (redcol * 1.5 + 0.3) % 1.0;
+                greencol = (greencol * 1.3 + 0.4) % 1.0;
+            }
+        }
+    }
+}
+
+pub fn label_clusters(graph: &mut Graph) {
+    let mut node_ids: Vec<usize> = Vec::new();
+
+    for node in graph.iter() {
+        node_ids.push(node.id);
+    }
+    let mut count = 1;
+    // println!("{}", node_ids.len());
+    for node_id in node_ids {
+        // println!("Node {}", node_id);
+        clusters(graph, node_id, count);
+        count += 1;
+    }
+    // println!("{:?}", graph);
+}
+
+fn clusters(graph: &mut Graph, node_id: usize, cluster_id: usize) {
+    if graph[node_id].cluster_id.is_some() {
+        return;
+    }
+
+    graph[node_id].cluster_id = Some(cluster_id);
+    // println!("Node {} assigned to cluster {}", node_id, cluster_id);
+
+    // Clusters ignore direction, a note belongs with the notes that link to it too
+    let links: Vec<usize> = graph[node_id].neighbours().collect();
+
+    for link in links {
+        if link != node_id {
+            clusters(graph, link, cluster_id);
+        }
+    }
+}
+
+fn dfs(graph: &mut Graph, node_id: usize, col: f32, redcol: f32, greencol: f32) {
+    let links_to_visit: Vec<usize> = {
+        let node = &graph[node_id];
+        node.neighbours()
+            .filter_map(|id| {
+                if graph[id].color[2] == 0.0 {
+                    Some(id)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    };
+
+    // Update the color of the current node
+    graph[node_id].color[0] = redcol;
+    graph[node_id].color[1] = greencol;
+    graph[node_id].color[2] = col;
+
+    for id in links_to_visit {
+        graph[id].color[0] = redcol;
+        graph[id].color[1] = greencol;
+        graph[id].color[2] = col;
+        dfs(graph, id, col, redcol, greencol);
+    }
+}
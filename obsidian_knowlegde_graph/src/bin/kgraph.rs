@@ -0,0 +1,256 @@
+use clap::{Parser, Subcommand};
+use egui::Pos2;
+use obsidian_knowlegde_graph::analysis::{fix_graph, prepare};
+use obsidian_knowlegde_graph::data::Graph;
+use obsidian_knowlegde_graph::export::{write_graph, ExportFormat};
+use obsidian_knowlegde_graph::layout::{headless_layout, initialize_positions};
+use obsidian_knowlegde_graph::render::{write_render, RenderFormat, RenderOptions};
+use obsidian_knowlegde_graph::similarity::{self, DEFAULT_THRESHOLD};
+use obsidian_knowlegde_graph::source::SourceArgs;
+use obsidian_knowlegde_graph::stats;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(name = "kgraph", version, about = "Reports on a knowledge graph without opening a window.", long_about = None)]
+/// CLI
+struct Cli {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    /// Print reports as JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Print layout progress to stderr
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Node, link, orphan and component counts
+    Stats,
+    /// Nodes without any links in or out
+    Orphans,
+    /// Links that point at something that isn't a note
+    Dangling,
+    /// Link targets that aren't notes, with the notes that link to them
+    Unresolved,
+    /// Nodes with the most links pointing at them
+    Top {
+        /// How many nodes to list
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Sizes of the connected groups of nodes, biggest first
+    Components,
+    /// Notes whose text is alike but that don't link each other, most alike first
+    Similar {
+        /// How alike two notes have to be, from 0 to 1
+        #[arg(short, long, default_value_t = DEFAULT_THRESHOLD)]
+        threshold: f32,
+    },
+    /// Write the graph to a GraphML, GEXF, DOT or JSON file
+    Export {
+        /// File to write the graph to
+        output: PathBuf,
+        /// Format of the file, guessed from the file extension when left out
+        #[arg(short, long, value_enum)]
+        format: Option<ExportFormat>,
+        /// Number of layout iterations to run for the node positions
+        #[arg(short, long, default_value_t = 5000)]
+        iterations: usize,
+    },
+    /// Draw the graph to an SVG or PNG image
+    Render {
+        /// Image file to write
+        output: PathBuf,
+        /// Format of the image, guessed from the file extension when left out
+        #[arg(short, long, value_enum)]
+        format: Option<RenderFormat>,
+        /// Width of the image in pixels
+        #[arg(long, default_value_t = 1600)]
+        width: u32,
+        /// Height of the image in pixels
+        #[arg(long, default_value_t = 1200)]
+        height: u32,
+        /// Number of layout iterations to run for the node positions
+        #[arg(short, long, default_value_t = 5000)]
+        iterations: usize,
+        /// Use the x and y saved in the input file instead of running the layout
+        #[arg(long)]
+        saved_layout: bool,
+        /// Leave out the note titles
+        #[arg(long)]
+        no_labels: bool,
+        /// Draw links that go both ways as a pair of curved arrows
+        #[arg(long)]
+        arrows: bool,
+    },
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let graph = fix_graph(cli.source.load());
+
+    match cli.command {
+        Command::Stats => {
+            let summary = stats::summary(&graph);
+            report(cli.json, &summary, || {
+                println!("nodes:             {}", summary.nodes);
+                println!("notes:             {}", summary.notes);
+                println!("links:             {}", summary.links);
+                println!("orphans:           {}", summary.orphans);
+                println!("dangling links:    {}", summary.dangling_links);
+                println!("unresolved:        {}", summary.unresolved);
+                println!("components:        {}", summary.components);
+                println!("largest component: {}", summary.largest_component);
+            });
+        }
+        Command::Orphans => {
+            let orphans = stats::orphans(&graph);
+            report(cli.json, &orphans, || {
+                for node in &orphans {
+                    println!("{}", node.title);
+                }
+            });
+        }
+        Command::Dangling => {
+            let dangling = stats::dangling(&graph);
+            report(cli.json, &dangling, || {
+                for link in &dangling {
+                    println!("{} -> {}", link.source.title, link.target.title);
+                }
+            });
+        }
+        Command::Unresolved => {
+            let unresolved = stats::unresolved(&graph);
+            report(cli.json, &unresolved, || {
+                for entry in &unresolved {
+                    println!("{}", entry.target.title);
+                    for source in &entry.referenced_by {
+                        println!("    {}", source.title);
+                    }
+                }
+            });
+        }
+        Command::Top { limit } => {
+            let top = stats::most_linked(&graph, limit);
+            report(cli.json, &top, || {
+                for node in &top {
+                    println!(
+                        "{:>5} in {:>5} out  {}",
+                        node.incoming, node.outgoing, node.title
+                    );
+                }
+            });
+        }
+        Command::Components => {
+            let sizes = stats::component_sizes(&graph);
+            report(cli.json, &sizes, || {
+                for size in &sizes {
+                    println!("{}", size);
+                }
+            });
+        }
+        Command::Similar { threshold } => {
+            let suggestions = similarity::suggestions(&graph, threshold);
+            report(cli.json, &suggestions, || {
+                for pair in &suggestions {
+                    println!("{:.2}  {}  ~  {}", pair.score, pair.a.title, pair.b.title);
+                }
+            });
+        }
+        Command::Export {
+            output,
+            format,
+            iterations,
+        } => export(graph, &output, format, iterations, cli.verbose),
+        Command::Render {
+            output,
+            format,
+            width,
+            height,
+            iterations,
+            saved_layout,
+            no_labels,
+            arrows,
+        } => {
+            let options = RenderOptions {
+                width,
+                height,
+                labels: !no_labels,
+                arrows,
+            };
+            let layout = if saved_layout { None } else { Some(iterations) };
+            render(graph, &output, format, &options, layout, cli.verbose);
+        }
+    }
+}
+
+fn report<T: Serialize>(json: bool, value: &T, text: impl FnOnce()) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(value).unwrap());
+    } else {
+        text();
+    }
+}
+
+fn export(
+    graph: Graph,
+    output: &Path,
+    format: Option<ExportFormat>,
+    iterations: usize,
+    verbose: bool,
+) {
+    let Some(format) = format.or_else(|| ExportFormat::from_path(output)) else {
+        eprintln!(
+            "Can't tell the format of {} from its extension, pass --format",
+            output.display()
+        );
+        std::process::exit(1);
+    };
+    let mut graph = prepare(graph);
+    let positions = headless_layout(&mut graph, iterations, verbose);
+    if let Err(e) = write_graph(output, &graph, &positions, format) {
+        eprintln!("Couldn't write {}: {}", output.display(), e);
+        std::process::exit(1);
+    }
+}
+
+// layout is the number of spring layout iterations to run, None keeps the positions the
+// graph was loaded with
+fn render(
+    graph: Graph,
+    output: &Path,
+    format: Option<RenderFormat>,
+    options: &RenderOptions,
+    layout: Option<usize>,
+    verbose: bool,
+) {
+    let Some(format) = format.or_else(|| RenderFormat::from_path(output)) else {
+        eprintln!(
+            "Can't tell the image format of {} from its extension, pass --format",
+            output.display()
+        );
+        std::process::exit(1);
+    };
+    let mut graph = prepare(graph);
+    let positions = match layout {
+        Some(iterations) => headless_layout(&mut graph, iterations, verbose),
+        None => {
+            // Only run for the unlinked node filtering the viewer does, the saved positions
+            // are kept
+            initialize_positions(&mut graph);
+            graph.iter().map(|node| Pos2::new(node.x, node.y)).collect()
+        }
+    };
+    if let Err(e) = write_render(output, &graph, &positions, options, format) {
+        eprintln!("Couldn't write {}: {}", output.display(), e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,91 @@
+use eframe::egui;
+use egui::{Pos2, Rect, Vec2};
+
+const MIN_ZOOM: f32 = 0.02;
+const MAX_ZOOM: f32 = 20.0;
+// How quickly a fling slows down, higher stops sooner
+const FRICTION: f32 = 5.0;
+// Below this many screen points a second the camera just stops
+const MIN_SPEED: f32 = 5.0;
+
+// Where the window is looking in layout space. Drawing and hit testing both go through
+// world_to_screen so what you see is what you click
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Camera {
+    // Layout position shown in the middle of the viewport
+    pub center: Pos2,
+    pub zoom: f32,
+    // Part of the window the graph is drawn in
+    pub viewport: Rect,
+    // Screen points a second left over from a drag, decays every frame
+    velocity: Vec2,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Self {
+            center: Pos2::new(400.0, 300.0),
+            zoom: 1.0,
+            viewport: Rect::from_min_size(Pos2::ZERO, Vec2::new(800.0, 600.0)),
+            velocity: Vec2::ZERO,
+        }
+    }
+
+    pub fn world_to_screen(&self, pos: Pos2) -> Pos2 {
+        self.viewport.center() + (pos - self.center) * self.zoom
+    }
+
+    pub fn screen_to_world(&self, pos: Pos2) -> Pos2 {
+        self.center + (pos - self.viewport.center()) / self.zoom
+    }
+
+    // Moves the graph along with the mouse, delta is in screen points
+    pub fn pan_by(&mut self, delta: Vec2) {
+        self.center -= delta / self.zoom;
+    }
+
+    // Zooms so that the layout point under anchor stays under it
+    pub fn zoom_at(&mut self, anchor: Pos2, factor: f32) {
+        let world = self.screen_to_world(anchor);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.center = world - (anchor - self.viewport.center()) / self.zoom;
+    }
+
+    // Keeps the graph gliding after a drag is let go
+    pub fn fling(&mut self, velocity: Vec2) {
+        self.velocity = velocity;
+    }
+
+    pub fn stop(&mut self) {
+        self.velocity = Vec2::ZERO;
+    }
+
+    // Applies what is left of a fling, returns true while the camera is still moving
+    pub fn update(&mut self, dt: f32) -> bool {
+        if self.velocity.length() < MIN_SPEED {
+            self.velocity = Vec2::ZERO;
+            return false;
+        }
+        self.pan_by(self.velocity * dt);
+        self.velocity *= (-FRICTION * dt).exp();
+        true
+    }
+
+    // Centers on the layout rectangle and zooms so all of it is in view with a bit of margin
+    pub fn fit(&mut self, bounds: Rect) {
+        if !bounds.is_finite() || bounds.is_negative() {
+            return;
+        }
+        let size = bounds.size().max(Vec2::splat(1.0));
+        let zoom = (self.viewport.width() / size.x).min(self.viewport.height() / size.y) * 0.9;
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.center = bounds.center();
+        self.velocity = Vec2::ZERO;
+    }
+}
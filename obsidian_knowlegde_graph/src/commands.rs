@@ -0,0 +1,211 @@
+use eframe::egui;
+use egui::{Key, KeyboardShortcut, Modifiers};
+
+// Everything the viewer can do from the keyboard or the command palette
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    FitGraph,
+    ZoomIn,
+    ZoomOut,
+    NextNeighbour,
+    PreviousNeighbour,
+    FollowNeighbour,
+    Back,
+    ClearSelection,
+    TogglePreview,
+    ToggleArrows,
+    ToggleLabels,
+    ToggleMinimap,
+    ToggleUnresolved,
+    ToggleSimilar,
+    ToggleLayout,
+    TogglePathFinder,
+    ToggleLinkReport,
+    ToggleExport,
+    ToggleTimeline,
+    PlayTimeline,
+    CommandPalette,
+}
+
+impl Action {
+    pub const ALL: [Action; 21] = [
+        Action::FitGraph,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::NextNeighbour,
+        Action::PreviousNeighbour,
+        Action::FollowNeighbour,
+        Action::Back,
+        Action::ClearSelection,
+        Action::TogglePreview,
+        Action::ToggleArrows,
+        Action::ToggleLabels,
+        Action::ToggleMinimap,
+        Action::ToggleUnresolved,
+        Action::ToggleSimilar,
+        Action::ToggleLayout,
+        Action::TogglePathFinder,
+        Action::ToggleLinkReport,
+        Action::ToggleExport,
+        Action::ToggleTimeline,
+        Action::PlayTimeline,
+        Action::CommandPalette,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::FitGraph => "Fit graph to window",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::NextNeighbour => "Next neighbour",
+            Action::PreviousNeighbour => "Previous neighbour",
+            Action::FollowNeighbour => "Go to neighbour",
+            Action::Back => "Go back",
+            Action::ClearSelection => "Clear selection",
+            Action::TogglePreview => "Show or hide note preview",
+            Action::ToggleArrows => "Show or hide arrows",
+            Action::ToggleLabels => "Show or hide labels",
+            Action::ToggleMinimap => "Show or hide minimap",
+            Action::ToggleUnresolved => "Show or hide unresolved links",
+            Action::ToggleSimilar => "Show or hide similar notes",
+            Action::ToggleLayout => "Pause or resume layout",
+            Action::TogglePathFinder => "Open or close path finder",
+            Action::ToggleLinkReport => "Open or close link report",
+            Action::ToggleExport => "Open or close export",
+            Action::ToggleTimeline => "Open or close timeline",
+            Action::PlayTimeline => "Play or pause timeline",
+            Action::CommandPalette => "Command palette",
+        }
+    }
+
+    pub fn shortcut(self) -> KeyboardShortcut {
+        let (modifiers, key) = match self {
+            Action::FitGraph => (Modifiers::NONE, Key::F),
+            Action::ZoomIn => (Modifiers::NONE, Key::Equals),
+            Action::ZoomOut => (Modifiers::NONE, Key::Minus),
+            Action::NextNeighbour => (Modifiers::NONE, Key::CloseBracket),
+            Action::PreviousNeighbour => (Modifiers::NONE, Key::OpenBracket),
+            Action::FollowNeighbour => (Modifiers::NONE, Key::Enter),
+            Action::Back => (Modifiers::NONE, Key::Backspace),
+            Action::ClearSelection => (Modifiers::NONE, Key::Escape),
+            Action::TogglePreview => (Modifiers::NONE, Key::Space),
+            Action::ToggleArrows => (Modifiers::NONE, Key::A),
+            Action::ToggleLabels => (Modifiers::NONE, Key::L),
+            Action::ToggleMinimap => (Modifiers::NONE, Key::M),
+            Action::ToggleUnresolved => (Modifiers::NONE, Key::U),
+            Action::ToggleSimilar => (Modifiers::NONE, Key::S),
+            Action::ToggleLayout => (Modifiers::NONE, Key::P),
+            Action::TogglePathFinder => (Modifiers::SHIFT, Key::P),
+            Action::ToggleLinkReport => (Modifiers::SHIFT, Key::R),
+            Action::ToggleExport => (Modifiers::SHIFT, Key::E),
+            Action::ToggleTimeline => (Modifiers::NONE, Key::T),
+            Action::PlayTimeline => (Modifiers::SHIFT, Key::T),
+            Action::CommandPalette => (Modifiers::COMMAND, Key::P),
+        };
+        KeyboardShortcut::new(modifiers, key)
+    }
+
+    // Actions whose shortcut was pressed this frame. The shortcuts are consumed so a text box
+    // that gets focus later in the frame doesn't see them too
+    pub fn pressed(ctx: &egui::Context) -> Vec<Action> {
+        let typing = ctx.wants_keyboard_input();
+        ctx.input_mut(|input| {
+            let mut actions: Vec<Action> = Action::ALL
+                .into_iter()
+                // Longer shortcuts first so ctrl+P isn't taken as P
+                .rev()
+                .filter(|action| !typing || *action == Action::CommandPalette)
+                .filter(|action| input.consume_shortcut(&action.shortcut()))
+                .collect();
+            // + zooms in as well as =, for keyboards where + has its own key
+            if !typing && input.consume_key(Modifiers::NONE, Key::Plus) {
+                actions.push(Action::ZoomIn);
+            }
+            actions
+        })
+    }
+}
+
+// Searchable list of every action, opened with ctrl+P
+#[derive(Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    query: String,
+    highlighted: usize,
+}
+
+impl CommandPalette {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.highlighted = 0;
+    }
+
+    // Returns the action picked with enter or a click
+    pub fn ui(&mut self, ctx: &egui::Context) -> Option<Action> {
+        if !self.open {
+            return None;
+        }
+        let needle = self.query.to_lowercase();
+        let matches: Vec<Action> = Action::ALL
+            .into_iter()
+            .filter(|action| *action != Action::CommandPalette)
+            .filter(|action| action.name().to_lowercase().contains(&needle))
+            .collect();
+        self.highlighted = self.highlighted.min(matches.len().saturating_sub(1));
+
+        let (up, down, enter, escape) = ctx.input_mut(|input| {
+            (
+                input.consume_key(Modifiers::NONE, Key::ArrowUp),
+                input.consume_key(Modifiers::NONE, Key::ArrowDown),
+                input.consume_key(Modifiers::NONE, Key::Enter),
+                input.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+        if up {
+            self.highlighted = self.highlighted.saturating_sub(1);
+        }
+        if down && self.highlighted + 1 < matches.len() {
+            self.highlighted += 1;
+        }
+
+        let mut picked = None;
+        egui::Window::new("Commands")
+            .collapsible(false)
+            .resizable(false)
+            .title_bar(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 40.0])
+            .show(ctx, |ui| {
+                let search = ui.text_edit_singleline(&mut self.query);
+                search.request_focus();
+                if search.changed() {
+                    self.highlighted = 0;
+                }
+                for (n, action) in matches.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .selectable_label(n == self.highlighted, action.name())
+                            .clicked()
+                        {
+                            picked = Some(*action);
+                        }
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            ui.weak(ctx.format_shortcut(&action.shortcut()));
+                        });
+                    });
+                }
+            });
+
+        if enter {
+            picked = picked.or(matches.get(self.highlighted).copied());
+        }
+        if picked.is_some() || escape {
+            self.open = false;
+        }
+        picked
+    }
+}
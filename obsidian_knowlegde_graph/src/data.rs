@@ -6,29 +6,77 @@ use serde::{Deserialize, Serialize};
 
 pub type Graph = Vec<LinkNode>;
 
+// Everything but the id and title can be left out when reading a graph from JSON
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LinkNode {
     pub id: usize,
     pub title: String,
+    // Outgoing links, the notes this one links to
+    #[serde(default)]
     pub links: Vec<usize>,
+    // Incoming links, filled in from everyone else's links by prepare
+    #[serde(skip)]
+    pub backlinks: Vec<usize>,
+    #[serde(default)]
     pub color: [f32; 3],
+    #[serde(default)]
     pub cluster_id: Option<usize>,
+    #[serde(default = "default_internal")]
     pub internal: bool,
-
+    // False for link targets that no note in the vault goes by
+    #[serde(default = "default_resolved")]
+    pub resolved: bool,
+    // Milliseconds since 1970 when the note was last changed, if the source knows
+    #[serde(default)]
+    pub modified: Option<u64>,
+    // Milliseconds since 1970 when the note was made, if the source knows. Lockbook doesn't
+    // keep this, imported graphs can have it
+    #[serde(default)]
+    pub created: Option<u64>,
+    // When the node first shows up in the timeline, filled in by prepare. Notes use their own
+    // time and link targets show up with the first note that links to them
+    #[serde(skip)]
+    pub appeared: Option<u64>,
+    // Text of the note, only kept for the similarity pass. It can be given in JSON input but
+    // isn't written back out
+    #[serde(default, skip_serializing)]
+    pub body: String,
+    // Notes that read like this one without a link between them, filled in by
+    // add_similar_edges. Both ends list each other and they don't count as links anywhere
+    #[serde(skip)]
+    pub similar: Vec<usize>,
+
+    #[serde(default)]
     pub x: f32,
+    #[serde(default)]
     pub y: f32,
+    #[serde(default)]
     pub vx: f32,
+    #[serde(default)]
     pub vy: f32,
+    #[serde(default)]
     pub fx: Option<f32>,
+    #[serde(default)]
     pub fy: Option<f32>,
 }
 
+fn default_internal() -> bool {
+    true
+}
+
+fn default_resolved() -> bool {
+    true
+}
+
 #[derive(Clone, Debug)]
 pub struct Name_Id {
     pub id: usize,
     pub name: String,
     pub links: Vec<usize>,
     pub internal: bool,
+    pub resolved: bool,
+    pub modified: Option<u64>,
+    pub body: String,
 }
 
 impl Name_Id {
@@ -38,19 +86,29 @@ impl Name_Id {
             name,
             links,
             internal: true,
+            resolved: true,
+            modified: None,
+            body: String::new(),
         }
     }
 }
 
 impl LinkNode {
-    fn new(id: usize, title: String, links_given: Vec<usize>) -> Self {
+    pub(crate) fn new(id: usize, title: String, links_given: Vec<usize>) -> Self {
         LinkNode {
             id,
             title,
             links: links_given.clone(),
+            backlinks: Vec::new(),
             color: [0.0, 0.0, 0.0],
             cluster_id: None,
             internal: true,
+            resolved: true,
+            modified: None,
+            created: None,
+            appeared: None,
+            body: String::new(),
+            similar: Vec::new(),
 
             x: 0.0, // Set to an initial value, e.g., random or based on index
             y: 0.0, // Set to an initial value
@@ -60,9 +118,24 @@ impl LinkNode {
             fy: None,
         }
     }
+
+    // Everything linked to this node in either direction, each only once
+    pub fn neighbours(&self) -> impl Iterator<Item = usize> + '_ {
+        self.links.iter().copied().chain(
+            self.backlinks
+                .iter()
+                .copied()
+                .filter(|link| !self.links.contains(link)),
+        )
+    }
+
+    // Number of nodes linked to this one, ignoring direction
+    pub fn degree(&self) -> usize {
+        self.neighbours().count()
+    }
 }
 
-pub(crate) fn data() -> Graph {
+pub fn data() -> Graph {
     vec![
         // Subgraph 1
         LinkNode::new(0, String::from("Node 0"), vec![1, 2, 3, 4, 5]),
@@ -168,20 +241,20 @@ pub(crate) fn data() -> Graph {
     ]
 }
 
-pub(crate) fn lockbookdata() -> Graph {
+pub fn lockbookdata() -> Graph {
     let mut graph: Graph = Vec::new();
     let mut classify: Vec<Name_Id> = Vec::new();
     let core = core();
     let mut id: usize = 0;
     let mut num_links = 1;
-    let mut info: Vec<(String, String)> = Vec::new();
+    let mut info: Vec<(String, String, u64)> = Vec::new();
 
     for file in core.list_metadatas().unwrap() {
         if file.is_document() && file.name.ends_with(".md") {
             let doc = core.read_document(file.id).unwrap();
             let doc = String::from_utf8(doc).unwrap();
             let name = file.name;
-            info.push((name, doc));
+            info.push((name, doc, file.last_modified));
             //classify.push(Name_Id::new(classify.len(), name.clone(), vec![]));
         }
     }
@@ -191,10 +264,23 @@ pub(crate) fn lockbookdata() -> Graph {
         // Check for links in the document
         let doc = n.1;
         let name = n.0;
+        let modified = Some(n.2);
         let links = checkforlinks(&mut classify, &mut id, &doc);
-        id += 1;
         num_links += links.len();
-        classify.push(Name_Id::new(classify.len(), name.clone(), links));
+        if let Some(existing) = in_classify(&name, &classify) {
+            // A note before this one already linked here, so it's not a dangling target anymore
+            classify[existing].name = name.clone();
+            classify[existing].links = links;
+            classify[existing].resolved = true;
+            classify[existing].modified = modified;
+            classify[existing].body = doc;
+        } else {
+            id += 1;
+            let mut note = Name_Id::new(classify.len(), name.clone(), links);
+            note.modified = modified;
+            note.body = doc;
+            classify.push(note);
+        }
         //add_links(links, &mut getName_Id(&name, &classify));
         // println!("{:?}", getName_Id(&name, &classify));
         //getName_Id(&name, &classify).links = links.clone;
@@ -247,6 +333,10 @@ pub(crate) fn lockbookdata() -> Graph {
         } else {
             graph.push(LinkNode::new(item.id, item.name.to_string(), links));
         }
+        let node = graph.last_mut().unwrap();
+        node.resolved = item.resolved;
+        node.modified = item.modified;
+        node.body = item.body.clone();
         //println!("graph      {:?}\n", graph)
     }
     //ensure_bidirectional_links(&mut graph);
@@ -323,7 +413,9 @@ fn checkforlinks(classify: &mut Vec<Name_Id>, id: &mut usize, doc: &str) -> Vec<
             *id += 1;
             // If link not found, add it
             //println!("New link found: {}", &link);
-            classify.push(Name_Id::new(classify.len(), link.clone(), vec![]));
+            let mut target = Name_Id::new(classify.len(), link.clone(), vec![]);
+            target.resolved = false;
+            classify.push(target);
             // println!("psuhing this linknode in {:?}", classify);
             // *id += 1;
         }
@@ -385,8 +477,8 @@ fn find_links(text: &str) -> Vec<String> {
         .find_iter(text)
         .map(|mat| {
             let url = mat.as_str().to_string();
-            // Extract the website name from the URL
-            extract_website_name(&url)
+            // Name the note or website the URL points at
+            link_target(&url)
         })
         .collect();
 
@@ -399,6 +491,29 @@ fn find_links(text: &str) -> Vec<String> {
     links
 }
 
+// Lockbook links name the note they go to, which can be anywhere in a folder, web links are
+// reduced to the website
+fn link_target(url: &str) -> String {
+    match url.strip_prefix("lb:") {
+        Some(path) => {
+            // The regex runs up to the next space, so markdown can leave brackets on the end
+            let path = path.trim_end_matches(|c: char| matches!(c, ')' | ']' | '>' | ',' | '.'));
+            let name = path.rsplit('/').next().unwrap_or(path);
+            name.replace("%20", " ")
+        }
+        None => extract_website_name(url),
+    }
+}
+
+// Note names and link targets are matched on this, so a link to `ideas` finds `Ideas.md`
+fn note_key(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    match name.strip_suffix(".md") {
+        Some(stem) => stem.to_string(),
+        None => name,
+    }
+}
+
 fn extract_website_name(url: &str) -> String {
     // Remove "https://" or "http://" or "www." from the URL
     let domain = url
@@ -422,8 +537,9 @@ fn extract_website_name(url: &str) -> String {
 fn in_classify(name: &String, classify: &Vec<Name_Id>) -> Option<usize> {
     // Search for the link in the classify vector and return its ID if found
     let mut id: Option<usize> = None;
+    let key = note_key(name);
     for linkinfo in classify {
-        if &linkinfo.name == name {
+        if note_key(&linkinfo.name) == key {
             let optional_num: Option<usize> = Some(linkinfo.id);
             id = optional_num;
             break;
@@ -462,3 +578,32 @@ fn writable_path() -> String {
 
     specified_path.or(default_path).unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockbook_links_name_the_note() {
+        assert_eq!(link_target("lb:Ideas.md"), "Ideas.md");
+        assert_eq!(link_target("lb:folder/My%20Note.md)"), "My Note.md");
+        assert_eq!(link_target("https://www.youtube.com/watch?v=1"), "youtube");
+    }
+
+    #[test]
+    fn notes_and_links_match_without_case_or_extension() {
+        assert_eq!(note_key("Ideas.md"), note_key("ideas"));
+        assert_eq!(note_key(" IDEAS.MD "), "ideas");
+        assert_ne!(note_key("ideas.md"), note_key("idea"));
+    }
+
+    #[test]
+    fn link_to_a_later_note_resolves() {
+        let mut classify = Vec::new();
+        let mut id = 0;
+        let links = checkforlinks(&mut classify, &mut id, "see lb:Later and https://a.com");
+        assert_eq!(links, [0, 1]);
+        assert!(!classify[0].resolved);
+        assert_eq!(in_classify(&"later.md".to_string(), &classify), Some(0));
+    }
+}
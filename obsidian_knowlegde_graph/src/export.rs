@@ -0,0 +1,385 @@
+use crate::data::{Graph, LinkNode};
+use eframe::egui;
+use egui::Pos2;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::sync::RwLock;
+
+// Version of the JSON document below, bump it when a field changes meaning
+pub const JSON_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum ExportFormat {
+    Graphml,
+    Gexf,
+    Dot,
+    Json,
+}
+
+// The JSON export. It is the serde form of LinkNode wrapped with a version:
+//
+// {
+//   "version": 1,
+//   "directed": true,
+//   "nodes": [
+//     {
+//       "id": 0,                 index of the node, links refer to these
+//       "title": "Note.md",      notes end in .md, everything else came from a link
+//       "links": [1, 4],         outgoing links
+//       "color": [0.1, 0.5, 1.0] rgb between 0 and 1
+//       "cluster_id": 3,         null for nodes without links
+//       "internal": true,
+//       "resolved": true,        false for link targets that no note goes by
+//       "modified": 1718000000000, milliseconds since 1970, null when not known
+//       "created": 1710000000000, same as modified
+//       "x": 120.5, "y": -30.0,  layout position
+//       "vx": 0.0, "vy": 0.0, "fx": null, "fy": null
+//     }
+//   ]
+// }
+#[derive(Serialize, Deserialize)]
+pub struct JsonGraph {
+    pub version: u32,
+    pub directed: bool,
+    pub nodes: Vec<LinkNode>,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::Graphml,
+        ExportFormat::Gexf,
+        ExportFormat::Dot,
+        ExportFormat::Json,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Graphml => "GraphML",
+            ExportFormat::Gexf => "GEXF",
+            ExportFormat::Dot => "Graphviz DOT",
+            ExportFormat::Json => "JSON",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Graphml => "graphml",
+            ExportFormat::Gexf => "gexf",
+            ExportFormat::Dot => "dot",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "graphml" | "xml" => Some(ExportFormat::Graphml),
+            "gexf" => Some(ExportFormat::Gexf),
+            "dot" | "gv" => Some(ExportFormat::Dot),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+// Window state for the export menu
+pub struct ExportMenu {
+    pub open: bool,
+    format: ExportFormat,
+    path: String,
+    status: String,
+}
+
+impl Default for ExportMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExportMenu {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            format: ExportFormat::Graphml,
+            path: String::from("graph.graphml"),
+            status: String::new(),
+        }
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context, graph: &Graph, positions: &RwLock<Vec<Pos2>>) {
+        let mut open = self.open;
+        egui::Window::new("Export").open(&mut open).show(ctx, |ui| {
+            for format in ExportFormat::ALL {
+                if ui
+                    .radio_value(&mut self.format, format, format.name())
+                    .changed()
+                {
+                    // Keep the file name in line with the chosen format
+                    let path = Path::new(&self.path).with_extension(format.extension());
+                    self.path = path.to_string_lossy().to_string();
+                }
+            }
+            ui.text_edit_singleline(&mut self.path);
+            if ui.button("Export").clicked() {
+                let positions = positions.read().unwrap().clone();
+                self.status =
+                    match write_graph(Path::new(&self.path), graph, &positions, self.format) {
+                        Ok(()) => format!("Wrote {}", self.path),
+                        Err(e) => format!("Export failed: {}", e),
+                    };
+            }
+            ui.label(&self.status);
+        });
+        self.open = open;
+    }
+}
+
+pub fn write_graph(
+    path: &Path,
+    graph: &Graph,
+    positions: &[Pos2],
+    format: ExportFormat,
+) -> std::io::Result<()> {
+    fs::write(path, export_graph(graph, positions, format))
+}
+
+pub fn export_graph(graph: &Graph, positions: &[Pos2], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Graphml => to_graphml(graph, positions),
+        ExportFormat::Gexf => to_gexf(graph, positions),
+        ExportFormat::Dot => to_dot(graph, positions),
+        ExportFormat::Json => to_json(graph, positions),
+    }
+}
+
+pub fn to_json(graph: &Graph, positions: &[Pos2]) -> String {
+    let nodes = graph
+        .iter()
+        .map(|node| {
+            let pos = position(positions, node.id);
+            LinkNode {
+                x: pos.x,
+                y: pos.y,
+                ..node.clone()
+            }
+        })
+        .collect();
+    let document = JsonGraph {
+        version: JSON_VERSION,
+        directed: true,
+        nodes,
+    };
+    serde_json::to_string_pretty(&document).unwrap()
+}
+
+pub fn to_graphml(graph: &Graph, positions: &[Pos2]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    let keys = [
+        ("title", "string"),
+        ("x", "float"),
+        ("y", "float"),
+        ("color", "string"),
+        ("cluster", "int"),
+        ("internal", "boolean"),
+        ("note", "boolean"),
+        ("resolved", "boolean"),
+        ("modified", "long"),
+        ("created", "long"),
+    ];
+    for (name, kind) in keys {
+        let _ = writeln!(
+            out,
+            "  <key id=\"{name}\" for=\"node\" attr.name=\"{name}\" attr.type=\"{kind}\"/>"
+        );
+    }
+    out.push_str("  <graph id=\"G\" edgedefault=\"directed\">\n");
+    for node in graph {
+        let pos = position(positions, node.id);
+        let _ = writeln!(out, "    <node id=\"n{}\">", node.id);
+        let _ = writeln!(
+            out,
+            "      <data key=\"title\">{}</data>",
+            xml_escape(&node.title)
+        );
+        let _ = writeln!(out, "      <data key=\"x\">{}</data>", pos.x);
+        let _ = writeln!(out, "      <data key=\"y\">{}</data>", pos.y);
+        let _ = writeln!(
+            out,
+            "      <data key=\"color\">{}</data>",
+            hex_color(node.color)
+        );
+        if let Some(cluster) = node.cluster_id {
+            let _ = writeln!(out, "      <data key=\"cluster\">{}</data>", cluster);
+        }
+        let _ = writeln!(out, "      <data key=\"internal\">{}</data>", node.internal);
+        let _ = writeln!(out, "      <data key=\"note\">{}</data>", is_note(node));
+        let _ = writeln!(out, "      <data key=\"resolved\">{}</data>", node.resolved);
+        if let Some(modified) = node.modified {
+            let _ = writeln!(out, "      <data key=\"modified\">{}</data>", modified);
+        }
+        if let Some(created) = node.created {
+            let _ = writeln!(out, "      <data key=\"created\">{}</data>", created);
+        }
+        out.push_str("    </node>\n");
+    }
+    for (n, (from, to)) in edges(graph).enumerate() {
+        let _ = writeln!(
+            out,
+            "    <edge id=\"e{n}\" source=\"n{from}\" target=\"n{to}\"/>"
+        );
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+pub fn to_gexf(graph: &Graph, positions: &[Pos2]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(
+        "<gexf xmlns=\"http://gexf.net/1.3\" xmlns:viz=\"http://gexf.net/1.3/viz\" version=\"1.3\">\n",
+    );
+    out.push_str("  <graph defaultedgetype=\"directed\" mode=\"static\">\n");
+    out.push_str("    <attributes class=\"node\">\n");
+    out.push_str("      <attribute id=\"cluster\" title=\"cluster\" type=\"integer\"/>\n");
+    out.push_str("      <attribute id=\"internal\" title=\"internal\" type=\"boolean\"/>\n");
+    out.push_str("      <attribute id=\"note\" title=\"note\" type=\"boolean\"/>\n");
+    out.push_str("      <attribute id=\"resolved\" title=\"resolved\" type=\"boolean\"/>\n");
+    out.push_str("    </attributes>\n");
+    out.push_str("    <nodes>\n");
+    for node in graph {
+        let pos = position(positions, node.id);
+        let [r, g, b] = rgb(node.color);
+        let _ = writeln!(
+            out,
+            "      <node id=\"{}\" label=\"{}\">",
+            node.id,
+            xml_escape(&node.title)
+        );
+        out.push_str("        <attvalues>\n");
+        if let Some(cluster) = node.cluster_id {
+            let _ = writeln!(
+                out,
+                "          <attvalue for=\"cluster\" value=\"{}\"/>",
+                cluster
+            );
+        }
+        let _ = writeln!(
+            out,
+            "          <attvalue for=\"internal\" value=\"{}\"/>",
+            node.internal
+        );
+        let _ = writeln!(
+            out,
+            "          <attvalue for=\"note\" value=\"{}\"/>",
+            is_note(node)
+        );
+        let _ = writeln!(
+            out,
+            "          <attvalue for=\"resolved\" value=\"{}\"/>",
+            node.resolved
+        );
+        out.push_str("        </attvalues>\n");
+        let _ = writeln!(out, "        <viz:color r=\"{r}\" g=\"{g}\" b=\"{b}\"/>");
+        // GEXF has y pointing up, the screen has it pointing down
+        let _ = writeln!(
+            out,
+            "        <viz:position x=\"{}\" y=\"{}\" z=\"0.0\"/>",
+            pos.x, -pos.y
+        );
+        let _ = writeln!(
+            out,
+            "        <viz:size value=\"{}\"/>",
+            (node.links.len() as f32 + 3.0).sqrt()
+        );
+        out.push_str("      </node>\n");
+    }
+    out.push_str("    </nodes>\n    <edges>\n");
+    for (n, (from, to)) in edges(graph).enumerate() {
+        let _ = writeln!(
+            out,
+            "      <edge id=\"{n}\" source=\"{from}\" target=\"{to}\"/>"
+        );
+    }
+    out.push_str("    </edges>\n  </graph>\n</gexf>\n");
+    out
+}
+
+pub fn to_dot(graph: &Graph, positions: &[Pos2]) -> String {
+    let mut out = String::new();
+    out.push_str("digraph knowledge_graph {\n");
+    out.push_str("  node [shape=circle, style=filled];\n");
+    for node in graph {
+        let pos = position(positions, node.id);
+        let cluster = node
+            .cluster_id
+            .map_or(String::new(), |cluster| format!(", cluster={}", cluster));
+        // Unresolved targets get a dashed outline like in the viewer
+        let style = if node.resolved {
+            ""
+        } else {
+            ", style=\"filled,dashed\""
+        };
+        // Graphviz positions are in points with y pointing up, the ! pins them for neato
+        let _ = writeln!(
+            out,
+            "  n{} [label=\"{}\", pos=\"{},{}!\", fillcolor=\"{}\", internal={}, note={}, resolved={}{}{}];",
+            node.id,
+            dot_escape(&node.title),
+            pos.x,
+            -pos.y,
+            hex_color(node.color),
+            node.internal,
+            is_note(node),
+            node.resolved,
+            style,
+            cluster
+        );
+    }
+    for (from, to) in edges(graph) {
+        let _ = writeln!(out, "  n{from} -> n{to};");
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn edges(graph: &Graph) -> impl Iterator<Item = (usize, usize)> + '_ {
+    graph.iter().flat_map(|node| {
+        node.links
+            .iter()
+            .filter(|&&link| link < graph.len())
+            .map(move |&link| (node.id, link))
+    })
+}
+
+fn position(positions: &[Pos2], id: usize) -> Pos2 {
+    positions.get(id).copied().unwrap_or(Pos2::ZERO)
+}
+
+fn is_note(node: &LinkNode) -> bool {
+    node.title.ends_with(".md")
+}
+
+fn rgb(color: [f32; 3]) -> [u8; 3] {
+    color.map(|c| (c.clamp(0.0, 1.0) * 255.0) as u8)
+}
+
+fn hex_color(color: [f32; 3]) -> String {
+    let [r, g, b] = rgb(color);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,107 @@
+use eframe::egui;
+use egui::{Pos2, Vec2};
+
+// The points that make up an arrow between two nodes, clipped to the edge of both circles
+pub struct ArrowPoints {
+    pub start: Pos2,
+    pub base: Pos2,
+    pub tip: Pos2,
+    pub left: Pos2,
+    pub right: Pos2,
+}
+
+// Smallest node radius, shrinks as the graph grows so big vaults don't turn into one blob
+pub fn base_radius(node_count: usize) -> f32 {
+    (15.0) / ((node_count as f32).sqrt() / 3.0).max(1.0)
+}
+
+// Nodes with more links are drawn bigger
+pub fn node_size(base_size: f32, link_count: usize, zoom_factor: f32) -> f32 {
+    let k = 1.0;
+    let n = link_count as f32;
+    base_size + k * (n + 3.0).sqrt() * zoom_factor
+}
+
+// Straight arrow from the edge of the from circle to the edge of the to circle. size is the
+// radius of the node pointed at, self_size the radius of the node the arrow leaves
+pub fn arrow_points(
+    from: Pos2,
+    to: Pos2,
+    zoom_factor: f32,
+    size: f32,
+    self_size: f32,
+) -> Option<ArrowPoints> {
+    let direction = to - from;
+    let distance = direction.length();
+    // Circles that touch or overlap leave nothing to draw in between
+    if distance <= size + self_size {
+        return None;
+    }
+    let dir = direction / distance;
+    let start = from + dir * self_size;
+    let tip = to - dir * size;
+    let (base, left, right) = arrow_head(tip, dir, zoom_factor);
+    Some(ArrowPoints {
+        start,
+        base,
+        tip,
+        left,
+        right,
+    })
+}
+
+// A quadratic curve from start through control to base, with the head at the end
+pub struct CurvedArrowPoints {
+    pub start: Pos2,
+    pub control: Pos2,
+    pub base: Pos2,
+    pub tip: Pos2,
+    pub left: Pos2,
+    pub right: Pos2,
+}
+
+// Curved arrow for links that go both ways. bend is how far the middle of the curve sits off
+// the straight line as a fraction of its length. The side it bows to flips with the direction,
+// so the two links of a pair curve away from each other instead of drawing over one another
+pub fn curved_arrow_points(
+    from: Pos2,
+    to: Pos2,
+    zoom_factor: f32,
+    size: f32,
+    self_size: f32,
+    bend: f32,
+) -> Option<CurvedArrowPoints> {
+    let direction = to - from;
+    let distance = direction.length();
+    if distance <= size + self_size {
+        return None;
+    }
+    let dir = direction / distance;
+    let side = Vec2::new(dir.y, -dir.x);
+    let control = from + direction * 0.5 + side * bend * distance;
+    // The curve leaves and enters the circles heading towards the control point
+    let start = from + (control - from).normalized() * self_size;
+    let tip = to + (control - to).normalized() * size;
+    let (base, left, right) = arrow_head(tip, (tip - control).normalized(), zoom_factor);
+    Some(CurvedArrowPoints {
+        start,
+        control,
+        base,
+        tip,
+        left,
+        right,
+    })
+}
+
+// Base and the two back corners of an arrow head pointing along dir
+fn arrow_head(tip: Pos2, dir: Vec2, zoom_factor: f32) -> (Pos2, Pos2, Pos2) {
+    let arrow_length = 6.0 * zoom_factor;
+    let arrow_width = 4.0 * zoom_factor;
+    let base = tip - dir * arrow_length;
+    let perp = Vec2::new(-dir.y, dir.x);
+    (
+        base,
+        base + perp * (arrow_width / 2.0),
+        base - perp * (arrow_width / 2.0),
+    )
+}
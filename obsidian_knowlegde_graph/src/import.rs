@@ -0,0 +1,501 @@
+use crate::data::{Graph, LinkNode};
+use crate::export::JsonGraph;
+use eframe::egui;
+use egui::ahash::{HashMap, HashMapExt};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum ImportFormat {
+    Json,
+    Graphml,
+    Csv,
+}
+
+impl ImportFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "json" => Some(ImportFormat::Json),
+            "graphml" | "xml" => Some(ImportFormat::Graphml),
+            "csv" | "tsv" => Some(ImportFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Xml(quick_xml::Error),
+    Csv(csv::Error),
+    UnknownFormat,
+    Invalid(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(e) => write!(f, "{}", e),
+            ImportError::Json(e) => write!(f, "invalid JSON: {}", e),
+            ImportError::Xml(e) => write!(f, "invalid GraphML: {}", e),
+            ImportError::Csv(e) => write!(f, "invalid CSV: {}", e),
+            ImportError::UnknownFormat => {
+                write!(f, "can't tell the format from the file extension")
+            }
+            ImportError::Invalid(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<std::io::Error> for ImportError {
+    fn from(e: std::io::Error) -> Self {
+        ImportError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ImportError {
+    fn from(e: serde_json::Error) -> Self {
+        ImportError::Json(e)
+    }
+}
+
+impl From<quick_xml::Error> for ImportError {
+    fn from(e: quick_xml::Error) -> Self {
+        ImportError::Xml(e)
+    }
+}
+
+impl From<csv::Error> for ImportError {
+    fn from(e: csv::Error) -> Self {
+        ImportError::Csv(e)
+    }
+}
+
+// A link that pointed at a node the file doesn't have, it gets left out of the graph
+#[derive(Debug, Clone)]
+pub struct DanglingLink {
+    pub source: String,
+    pub target: String,
+}
+
+// A value the node couldn't use, it keeps its default for that field instead
+#[derive(Debug, Clone)]
+pub struct InvalidValue {
+    pub node: String,
+    pub field: &'static str,
+    pub value: String,
+}
+
+// Things that were wrong with the file but didn't stop it from loading
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub dangling: Vec<DanglingLink>,
+    pub invalid: Vec<InvalidValue>,
+}
+
+impl ImportReport {
+    pub fn print(&self) {
+        for link in &self.dangling {
+            eprintln!(
+                "warning: {} links to {} which is not in the graph",
+                link.source, link.target
+            );
+        }
+        for value in &self.invalid {
+            eprintln!(
+                "warning: {} has an invalid {} {:?}",
+                value.node, value.field, value.value
+            );
+        }
+    }
+}
+
+pub fn import_graph(
+    path: &Path,
+    format: Option<ImportFormat>,
+) -> Result<(Graph, ImportReport), ImportError> {
+    let format = format
+        .or_else(|| ImportFormat::from_path(path))
+        .ok_or(ImportError::UnknownFormat)?;
+    let text = fs::read_to_string(path)?;
+    match format {
+        ImportFormat::Json => from_json(&text),
+        ImportFormat::Graphml => from_graphml(&text),
+        ImportFormat::Csv => from_csv(&text, path.extension().is_some_and(|e| e == "tsv")),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonInput {
+    Document(JsonGraph),
+    Nodes(Vec<LinkNode>),
+}
+
+// Reads the export document or a bare list of nodes. Ids don't have to be 0..n, they get
+// renumbered so that a node's id is its index like the rest of the code expects. Links from a
+// node to itself are dropped like the other formats do
+pub fn from_json(text: &str) -> Result<(Graph, ImportReport), ImportError> {
+    let nodes = match serde_json::from_str(text)? {
+        JsonInput::Document(document) => document.nodes,
+        JsonInput::Nodes(nodes) => nodes,
+    };
+
+    let mut index = HashMap::new();
+    for (i, node) in nodes.iter().enumerate() {
+        if index.insert(node.id, i).is_some() {
+            return Err(ImportError::Invalid(format!(
+                "node id {} is used more than once",
+                node.id
+            )));
+        }
+    }
+
+    let mut report = ImportReport::default();
+    let mut graph = Graph::new();
+    for (i, node) in nodes.iter().enumerate() {
+        let mut links = Vec::new();
+        for link in &node.links {
+            match index.get(link) {
+                Some(&target) if target != i && !links.contains(&target) => links.push(target),
+                Some(_) => {}
+                None => report.dangling.push(DanglingLink {
+                    source: node.id.to_string(),
+                    target: link.to_string(),
+                }),
+            }
+        }
+        graph.push(LinkNode {
+            id: i,
+            links,
+            ..node.clone()
+        });
+    }
+    Ok((graph, report))
+}
+
+// Collects nodes by name so edge lists and GraphML can refer to them by string ids
+struct GraphBuilder {
+    graph: Graph,
+    index: HashMap<String, usize>,
+}
+
+impl GraphBuilder {
+    fn new() -> Self {
+        Self {
+            graph: Graph::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    fn node(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.index.get(name) {
+            return id;
+        }
+        let id = self.graph.len();
+        self.graph
+            .push(LinkNode::new(id, name.to_string(), Vec::new()));
+        self.index.insert(name.to_string(), id);
+        id
+    }
+
+    fn link(&mut self, from: usize, to: usize) {
+        if from != to && !self.graph[from].links.contains(&to) {
+            self.graph[from].links.push(to);
+        }
+    }
+}
+
+// Source and target columns, an optional header row is skipped. A row with an empty
+// target adds a node without links
+pub fn from_csv(text: &str, tabs: bool) -> Result<(Graph, ImportReport), ImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .delimiter(if tabs { b'\t' } else { b',' })
+        .from_reader(text.as_bytes());
+
+    let mut builder = GraphBuilder::new();
+    for (row, record) in reader.records().enumerate() {
+        let record = record?;
+        let source = record.get(0).unwrap_or("");
+        let target = record.get(1).unwrap_or("");
+        if row == 0 && is_header(source, target) {
+            continue;
+        }
+        if source.is_empty() {
+            continue;
+        }
+        let from = builder.node(source);
+        if !target.is_empty() {
+            let to = builder.node(target);
+            builder.link(from, to);
+        }
+    }
+    Ok((builder.graph, ImportReport::default()))
+}
+
+fn is_header(source: &str, target: &str) -> bool {
+    let source = source.to_lowercase();
+    let target = target.to_lowercase();
+    matches!(source.as_str(), "source" | "from" | "src")
+        && matches!(target.as_str(), "target" | "to" | "dst")
+}
+
+// What a GraphML <key> is for, only the ones the viewer can use are kept
+enum GraphmlKey {
+    Title,
+    X,
+    Y,
+    Color,
+    Resolved,
+    Modified,
+    Created,
+}
+
+pub fn from_graphml(text: &str) -> Result<(Graph, ImportReport), ImportError> {
+    let mut reader = Reader::from_str(text);
+    reader.trim_text(true);
+
+    let mut keys: HashMap<String, GraphmlKey> = HashMap::new();
+    let mut builder = GraphBuilder::new();
+    let mut edges: Vec<(String, String, bool)> = Vec::new();
+    let mut report = ImportReport::default();
+    let mut undirected = false;
+    // The node being read and the key of the <data> element we are inside of
+    let mut current: Option<usize> = None;
+    let mut data_key: Option<String> = None;
+
+    loop {
+        let event = reader.read_event()?;
+        // A self-closing element has no End event, so nothing it starts may outlive it
+        let empty = matches!(event, Event::Empty(_));
+        match event {
+            Event::Start(e) | Event::Empty(e) => match e.name().as_ref() {
+                b"key" => {
+                    // Edge and graph keys can share names like label with node keys
+                    let target = attribute(&e, "for")?;
+                    if !matches!(target.as_deref(), None | Some("node") | Some("all")) {
+                        continue;
+                    }
+                    let id = attribute(&e, "id")?.unwrap_or_default();
+                    let name = attribute(&e, "attr.name")?.unwrap_or_else(|| id.clone());
+                    let kind = match name.to_lowercase().as_str() {
+                        "title" | "label" | "name" => GraphmlKey::Title,
+                        "x" => GraphmlKey::X,
+                        "y" => GraphmlKey::Y,
+                        "color" | "colour" => GraphmlKey::Color,
+                        "resolved" => GraphmlKey::Resolved,
+                        "modified" => GraphmlKey::Modified,
+                        "created" => GraphmlKey::Created,
+                        _ => continue,
+                    };
+                    keys.insert(id, kind);
+                }
+                b"graph" => {
+                    undirected = attribute(&e, "edgedefault")?.as_deref() == Some("undirected");
+                }
+                b"node" => {
+                    let id = attribute(&e, "id")?
+                        .ok_or_else(|| ImportError::Invalid("node without an id".into()))?;
+                    let node = builder.node(&id);
+                    current = (!empty).then_some(node);
+                }
+                b"edge" => {
+                    let source = attribute(&e, "source")?;
+                    let target = attribute(&e, "target")?;
+                    if let (Some(source), Some(target)) = (source, target) {
+                        let directed = attribute(&e, "directed")?;
+                        let mutual = directed.as_deref() == Some("false")
+                            || (undirected && directed.is_none());
+                        edges.push((source, target, mutual));
+                    }
+                }
+                b"data" if !empty => data_key = attribute(&e, "key")?,
+                _ => {}
+            },
+            Event::Text(t) => {
+                if let (Some(node), Some(key)) = (current, &data_key) {
+                    let value = t.unescape()?;
+                    let node = &mut builder.graph[node];
+                    let mut invalid = |field| {
+                        report.invalid.push(InvalidValue {
+                            node: node.title.clone(),
+                            field,
+                            value: value.to_string(),
+                        })
+                    };
+                    match keys.get(key) {
+                        Some(GraphmlKey::Title) => node.title = value.to_string(),
+                        Some(GraphmlKey::X) => match value.trim().parse() {
+                            Ok(x) => node.x = x,
+                            Err(_) => invalid("x"),
+                        },
+                        Some(GraphmlKey::Y) => match value.trim().parse() {
+                            Ok(y) => node.y = y,
+                            Err(_) => invalid("y"),
+                        },
+                        Some(GraphmlKey::Resolved) => node.resolved = value.trim() != "false",
+                        Some(GraphmlKey::Modified) => node.modified = value.trim().parse().ok(),
+                        Some(GraphmlKey::Created) => node.created = value.trim().parse().ok(),
+                        Some(GraphmlKey::Color) => match parse_hex_color(&value) {
+                            Some(color) => node.color = color,
+                            None => invalid("color"),
+                        },
+                        None => {}
+                    }
+                }
+            }
+            Event::End(e) => match e.name().as_ref() {
+                b"node" => current = None,
+                b"data" => data_key = None,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    // Edges are checked at the end since they may come before the nodes they use
+    for (source, target, mutual) in edges {
+        match (builder.index.get(&source), builder.index.get(&target)) {
+            (Some(&from), Some(&to)) => {
+                builder.link(from, to);
+                if mutual {
+                    builder.link(to, from);
+                }
+            }
+            _ => report.dangling.push(DanglingLink { source, target }),
+        }
+    }
+    Ok((builder.graph, report))
+}
+
+fn attribute(element: &BytesStart, name: &str) -> Result<Option<String>, ImportError> {
+    for attr in element.attributes() {
+        let attr = attr.map_err(quick_xml::Error::from)?;
+        if attr.key.as_ref() == name.as_bytes() {
+            return Ok(Some(attr.unescape_value()?.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+fn parse_hex_color(text: &str) -> Option<[f32; 3]> {
+    let hex = text.trim().strip_prefix('#')?;
+    // Slicing below is by byte, so anything but ASCII could split a character
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16)
+            .ok()
+            .map(|c| c as f32 / 255.0)
+    };
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(graph: &Graph) -> Vec<&str> {
+        graph.iter().map(|node| node.title.as_str()).collect()
+    }
+
+    #[test]
+    fn json_ids_are_renumbered() {
+        let (graph, report) = from_json(
+            r#"[{"id": 10, "title": "a", "links": [20, 10, 20, 30]}, {"id": 20, "title": "b"}]"#,
+        )
+        .unwrap();
+        assert_eq!(titles(&graph), ["a", "b"]);
+        assert_eq!(graph[0].id, 0);
+        assert_eq!(graph[1].id, 1);
+        // the self-link and the repeat are dropped, 30 isn't in the file
+        assert_eq!(graph[0].links, [1]);
+        assert_eq!(report.dangling.len(), 1);
+        assert_eq!(report.dangling[0].target, "30");
+    }
+
+    #[test]
+    fn json_export_document_and_repeated_ids() {
+        let (graph, _) = from_json(
+            r#"{"version": 1, "directed": true, "nodes": [{"id": 0, "title": "a", "created": 5}]}"#,
+        )
+        .unwrap();
+        assert_eq!(graph[0].created, Some(5));
+        assert!(from_json(r#"[{"id": 1, "title": "a"}, {"id": 1, "title": "b"}]"#).is_err());
+    }
+
+    #[test]
+    fn csv_header_is_only_skipped_when_it_is_one() {
+        let (graph, _) = from_csv("Source,Target\na,b\nb,c\nd,\n", false).unwrap();
+        assert_eq!(titles(&graph), ["a", "b", "c", "d"]);
+        assert_eq!(graph[0].links, [1]);
+        assert!(graph[3].links.is_empty());
+
+        let (graph, _) = from_csv("x,y\ny,x\n", false).unwrap();
+        assert_eq!(titles(&graph), ["x", "y"]);
+        assert_eq!(graph[1].links, [0]);
+
+        let (graph, _) = from_csv("from\tto\na\ta\n", true).unwrap();
+        assert_eq!(titles(&graph), ["a"]);
+        assert!(graph[0].links.is_empty());
+    }
+
+    const GRAPHML: &str = r#"<?xml version="1.0"?>
+<graphml>
+  <key id="label" for="edge" attr.name="label"/>
+  <key id="t" for="node" attr.name="title"/>
+  <key id="c" for="node" attr.name="color"/>
+  <graph edgedefault="undirected">
+    <node id="a"><data key="t">Alpha</data><data key="c">#ff0000</data></node>
+    <node id="b"/>
+    <node id="c"><data key="c">red</data></node>
+    <edge source="a" target="b"><data key="label">cites</data></edge>
+    <edge source="b" target="c" directed="true"/>
+    <edge source="c" target="z"/>
+  </graph>
+</graphml>"#;
+
+    #[test]
+    fn graphml_keys_only_apply_to_their_domain() {
+        let (graph, _) = from_graphml(GRAPHML).unwrap();
+        assert_eq!(titles(&graph), ["Alpha", "b", "c"]);
+        assert_eq!(graph[0].color, [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn graphml_edges_follow_the_default_direction() {
+        let (graph, report) = from_graphml(GRAPHML).unwrap();
+        assert_eq!(graph[0].links, [1]);
+        assert_eq!(graph[1].links, [0, 2]);
+        assert!(graph[2].links.is_empty());
+        assert_eq!(report.dangling.len(), 1);
+        assert_eq!(report.dangling[0].target, "z");
+    }
+
+    #[test]
+    fn graphml_bad_values_are_reported() {
+        let (_, report) = from_graphml(GRAPHML).unwrap();
+        assert_eq!(report.invalid.len(), 1);
+        assert_eq!(report.invalid[0].node, "c");
+        assert_eq!(report.invalid[0].field, "color");
+    }
+
+    #[test]
+    fn hex_colors() {
+        assert_eq!(parse_hex_color(" #00ff00 "), Some([0.0, 1.0, 0.0]));
+        assert_eq!(parse_hex_color("00ff00"), None);
+        assert_eq!(parse_hex_color("#aébbb"), None);
+        assert_eq!(parse_hex_color("#12345"), None);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::data::Graph;
+use crate::layout::Grid;
+use crate::scene::Scene;
+use eframe::egui;
+use egui::{Align2, Color32, FontId, Painter, Rect, Vec2};
+
+// Labels start showing once a node is this big on screen and are fully visible at LABEL_FULL.
+// Node size grows with zoom and with the number of links, so the busiest notes get their
+// titles first and the rest fade in as you zoom in
+const LABEL_MIN: f32 = 6.0;
+const LABEL_FULL: f32 = 10.0;
+// Keeps a zoomed out view of a huge vault from measuring thousands of titles a frame
+const MAX_LABELS: usize = 300;
+const MAX_TRIES: usize = 2000;
+
+pub struct Labels {
+    pub show: bool,
+    // Node ids, most linked first
+    order: Vec<usize>,
+    grid: Grid,
+    placed: Vec<Rect>,
+}
+
+impl Labels {
+    pub fn new(graph: &Graph) -> Self {
+        let mut order: Vec<usize> = (0..graph.len()).collect();
+        order.sort_by_key(|&id| std::cmp::Reverse(graph[id].degree()));
+        Self {
+            show: true,
+            order,
+            grid: Grid::new(64.0),
+            placed: Vec::new(),
+        }
+    }
+
+    // Titles go under their node. A label that would overlap one already placed is skipped,
+    // and since the important nodes are placed first they are the ones that win
+    pub fn paint(
+        &mut self,
+        painter: &Painter,
+        graph: &Graph,
+        scene: &Scene,
+        zoom_factor: f32,
+        hovered: Option<usize>,
+    ) {
+        if !self.show {
+            return;
+        }
+        self.grid.clear();
+        self.placed.clear();
+        let font_id = FontId::proportional(12.0 * zoom_factor.sqrt().clamp(0.75, 2.0));
+
+        let mut tries = 0;
+        for &id in &self.order {
+            if self.placed.len() >= MAX_LABELS || tries >= MAX_TRIES {
+                break;
+            }
+            let size = scene.sizes[id];
+            if size < LABEL_MIN {
+                // The order is by links and so is the size, nothing after this is bigger
+                break;
+            }
+            if !scene.is_visible(id) || Some(id) == hovered {
+                continue;
+            }
+            tries += 1;
+
+            let alpha = ((size - LABEL_MIN) / (LABEL_FULL - LABEL_MIN)).clamp(0.0, 1.0);
+            let color = Color32::WHITE.gamma_multiply(alpha);
+            let galley =
+                painter.layout_no_wrap(title(graph, id).to_string(), font_id.clone(), color);
+            let top = scene.screen[id] + Vec2::new(0.0, size + 2.0);
+            let rect = Align2::CENTER_TOP.anchor_size(top, galley.size());
+            if !painter.clip_rect().intersects(rect) {
+                continue;
+            }
+            if self
+                .grid
+                .query_rect(rect)
+                .any(|other| self.placed[other].intersects(rect))
+            {
+                continue;
+            }
+            self.grid.insert_rect(rect, self.placed.len());
+            self.placed.push(rect);
+            painter.galley(rect.min, galley, color);
+        }
+    }
+}
+
+fn title(graph: &Graph, id: usize) -> &str {
+    graph[id].title.trim_end_matches(".md")
+}
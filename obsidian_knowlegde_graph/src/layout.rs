@@ -0,0 +1,373 @@
+use crate::data::{Graph, LinkNode};
+use eframe::egui;
+use egui::ahash::{HashMap, HashMapExt};
+use egui::{Pos2, Rect, Vec2};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+
+// The makes it the code runs faster making it into grids
+pub struct Grid {
+    cell_size: f32,
+    grid: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl Grid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            grid: HashMap::new(),
+        }
+    }
+
+    pub fn insert_node(&mut self, pos: egui::Pos2, index: usize) {
+        let grid_pos = self.get_grid_pos(pos);
+        self.grid
+            .entry(grid_pos)
+            .or_insert_with(Vec::new)
+            .push(index);
+    }
+
+    pub fn get_grid_pos(&self, pos: egui::Pos2) -> (i32, i32) {
+        let x = (pos.x / self.cell_size).floor() as i32;
+        let y = (pos.y / self.cell_size).floor() as i32;
+        (x, y)
+    }
+
+    pub fn get_neighboring_cells(&self, pos: egui::Pos2) -> Vec<&Vec<usize>> {
+        let grid_pos = self.get_grid_pos(pos);
+        let mut neighboring_cells = Vec::new();
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if let Some(cell) = self.grid.get(&(grid_pos.0 + dx, grid_pos.1 + dy)) {
+                    neighboring_cells.push(cell);
+                }
+            }
+        }
+        neighboring_cells
+    }
+
+    // Adds the index to every cell the rectangle touches, for things that take up more room
+    // than a point like labels
+    pub fn insert_rect(&mut self, rect: Rect, index: usize) {
+        let (min, max) = (self.get_grid_pos(rect.min), self.get_grid_pos(rect.max));
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                self.grid.entry((x, y)).or_insert_with(Vec::new).push(index);
+            }
+        }
+    }
+
+    // Everything in the cells the rectangle touches, can hold the same index more than once
+    pub fn query_rect(&self, rect: Rect) -> impl Iterator<Item = usize> + '_ {
+        let (min, max) = (self.get_grid_pos(rect.min), self.get_grid_pos(rect.max));
+        (min.0..=max.0)
+            .flat_map(move |x| (min.1..=max.1).map(move |y| (x, y)))
+            .filter_map(|cell| self.grid.get(&cell))
+            .flatten()
+            .copied()
+    }
+
+    pub fn clear(&mut self) {
+        self.grid.clear();
+    }
+}
+
+// Shared between the layout thread and the window. generation is bumped after every write to
+// the positions so the window knows when its cached copy is out of date, paused holds the
+// layout where it is until it's cleared
+#[derive(Default)]
+pub struct LayoutControl {
+    pub generation: AtomicUsize,
+    pub paused: AtomicBool,
+}
+
+impl LayoutControl {
+    pub fn generation(&self) -> usize {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn toggle_pause(&self) {
+        self.paused.fetch_xor(true, Ordering::Relaxed);
+    }
+}
+
+// Runs the whole layout on this thread, for when there is no window to draw into. Nothing is
+// printed unless verbose is set, and then only to stderr so it doesn't mix with the output
+pub fn headless_layout(graph: &mut Graph, iterations: usize, verbose: bool) -> Vec<Pos2> {
+    let mut positions = initialize_positions(graph);
+    let num_nodes = graph.len() as f32;
+    for n in 0..iterations {
+        let (new_positions, total_change) = spring_step(graph, &positions);
+        positions = new_positions;
+        if verbose && n % 1000 == 0 {
+            eprintln!("Iteration: {}, Total Change: {:.4}", n, total_change);
+        }
+        if total_change < 0.01 * num_nodes {
+            if verbose {
+                eprintln!(
+                    "Converged after {} iterations with total change {:.4}",
+                    n, total_change
+                );
+            }
+            break;
+        }
+    }
+    positions
+}
+
+pub fn initialize_positions(graph: &mut Graph) -> Vec<Pos2> {
+    let width = 800.0;
+    let height = 600.0;
+
+    let main_center = Pos2::new(width / 2.0, height / 2.0);
+
+    let cluster_small_radius = 10.0;
+
+    let mut positions_map = HashMap::new();
+    let mut clusters: HashMap<Option<usize>, Vec<usize>> = HashMap::new();
+    let mut unlinked_nodes: Vec<usize> = Vec::new();
+
+    for node in graph.iter() {
+        if node.degree() == 0 {
+            unlinked_nodes.push(node.id);
+        } else {
+            clusters
+                .entry(node.cluster_id)
+                .or_insert_with(Vec::new)
+                .push(node.id);
+        }
+    }
+
+    let mut largest_cluster_id: Option<usize> = None;
+    let mut largest_cluster_size: usize = 0;
+
+    for (cluster_id, node_ids) in &clusters {
+        if node_ids.len() > largest_cluster_size {
+            largest_cluster_size = node_ids.len();
+            largest_cluster_id = *cluster_id;
+        }
+    }
+
+    let num_multi_clusters = clusters.len();
+    let main_circle_radius = 200.0;
+
+    if num_multi_clusters > 0 {
+        let angle_step_clusters = 2.0 * std::f32::consts::PI / num_multi_clusters as f32;
+
+        for (cluster_id, node_ids) in clusters {
+            let number_nodes = node_ids.len();
+            let angle_step_nodes = 2.0 * std::f32::consts::PI / number_nodes as f32;
+            let mut count: f32 = 0.0;
+
+            let is_largest = Some(cluster_id.unwrap()) == largest_cluster_id;
+
+            let cluster_center = if is_largest {
+                main_center
+            } else {
+                let angle = cluster_id.unwrap() as f32 * angle_step_clusters;
+                Pos2::new(
+                    main_center.x + main_circle_radius * angle.cos(),
+                    main_center.y + main_circle_radius * angle.sin(),
+                )
+            };
+
+            for node_id in node_ids {
+                let node_angle = count * angle_step_nodes;
+                let node_pos = Pos2::new(
+                    cluster_center.x + cluster_small_radius * node_angle.cos(),
+                    cluster_center.y + cluster_small_radius * node_angle.sin(),
+                );
+                positions_map.insert(node_id, node_pos);
+                count += 1.0;
+            }
+        }
+    }
+
+    let total_outer_nodes = unlinked_nodes.len();
+
+    if total_outer_nodes > 0 {
+        for (_i, &node_id) in unlinked_nodes.iter().enumerate() {
+            let nocluster: Option<usize> = None;
+            graph[node_id].cluster_id = nocluster;
+        }
+    }
+
+    (0..graph.len())
+        .map(|i| *positions_map.get(&i).unwrap_or(&main_center))
+        .collect()
+}
+
+pub fn apply_spring_layout(
+    thread_positions: Arc<RwLock<Vec<Pos2>>>,
+    control: Arc<LayoutControl>,
+    graph: &[LinkNode],
+    max_iterations: usize,
+) {
+    let num_nodes = graph.len() as f32;
+
+    for _n in 0..max_iterations {
+        while control.paused.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(50));
+        }
+        // Read current positions
+        let positions = {
+            let pos_lock = thread_positions.read().unwrap();
+            pos_lock.clone()
+        };
+
+        let (new_positions, total_change) = spring_step(graph, &positions);
+
+        // // Collision detection and resolution to prevent overlapping nodes
+        // let collision_cell_size = 2.0 * 20.0 + 5.0; // node_radius = 20.0, padding = 5.0
+        // let mut collision_grid = Grid::new(collision_cell_size);
+
+        // for (i, &pos) in new_positions.iter().enumerate() {
+        //     collision_grid.insert_node(pos, i);
+        // }
+
+        // for i in 0..graph.len() {
+        //     let pos_i = new_positions[i];
+
+        //     for cell in collision_grid.get_neighboring_cells(pos_i) {
+        //         for &j in cell {
+        //             if i != j {
+        //                 let delta = new_positions[i] - new_positions[j];
+        //                 let distance = delta.length();
+        //                 let min_distance = 2.0 * 20.0 + 5.0; // node_radius + padding
+
+        //                 if distance < min_distance {
+        //                     let overlap = min_distance - distance;
+        //                     let correction = delta.normalized() * (overlap / 2.0);
+        //                     new_positions[i] += correction;
+        //                     new_positions[j] -= correction;
+        //                 }
+        //             }
+        //         }
+        //     }
+        // }
+
+        // Write updated positions back to thread_positions
+        {
+            let mut pos_lock = thread_positions.write().unwrap();
+            *pos_lock = new_positions;
+        }
+        control.generation.fetch_add(1, Ordering::Release);
+
+        // Debugging: Print iteration and total change
+        if _n % 1000 == 0 {
+            println!("Iteration: {}, Total Change: {:.4}", _n, total_change);
+        }
+
+        // Convergence check
+        if total_change < 0.01 * num_nodes || _n >= max_iterations {
+            println!(
+                "Converged after {} iterations with total change {:.4}",
+                _n, total_change
+            );
+            break;
+        }
+        // if (_n == 10) {
+        //     break;
+        // }
+    }
+}
+
+// One step of the spring layout, giving the moved positions and how far the nodes were
+// pushed in total, which is what convergence is judged on
+fn spring_step(graph: &[LinkNode], positions: &[Pos2]) -> (Vec<Pos2>, f32) {
+    let width = 700.0;
+    let height = 500.0;
+    let num_nodes = graph.len() as f32;
+
+    // Spring and repulsion constants
+    let k_spring = 0.005;
+    let k_repel = 3.0;
+    let c = 0.05; // Scaling factor for movement
+    let max_movement = 100.0;
+
+    // Gravity parameters
+    let gravity_strength = 0.0001; // Adjust as needed
+    let center = Pos2::new(width / 2.0, height / 2.0);
+
+    let cell_size = (width * height / num_nodes).sqrt();
+    let mut grid = Grid::new(cell_size);
+
+    // Insert nodes into the grid for spatial partitioning
+    for (i, &pos) in positions.iter().enumerate() {
+        grid.insert_node(pos, i);
+    }
+
+    // Initialize forces
+    let mut forces = vec![Vec2::ZERO; graph.len()];
+
+    // Calculate repulsive forces
+    for i in 0..graph.len() {
+        let pos_i = positions[i];
+
+        for cell in grid.get_neighboring_cells(pos_i) {
+            for &j in cell {
+                if i != j {
+                    let delta = pos_i - positions[j];
+                    let distance = delta.length().max(0.01);
+
+                    // Repulsive force calculation (inverse quartic)
+                    let repulsive_force = k_repel / (distance * distance / 20.0);
+                    let repulsion = delta.normalized() * repulsive_force;
+
+                    forces[i] += repulsion;
+                    forces[j] -= repulsion;
+                }
+            }
+        }
+    }
+
+    // Calculate attractive forces. Springs don't care about direction, every linked pair
+    // pulls from both ends
+    for node in graph {
+        for link in node.neighbours() {
+            if link >= graph.len() {
+                continue;
+            }
+
+            let delta = positions[node.id] - positions[link];
+            let distance = delta.length().max(0.01);
+
+            // Attractive force calculation (custom formula)
+            let attractive_force = k_spring * distance * (distance / 20.0) as f32;
+            let attraction = delta.normalized() * attractive_force;
+
+            forces[node.id] -= attraction;
+            forces[link] += attraction;
+        }
+    }
+
+    // Apply gravity to pull nodes toward the center
+    for i in 0..graph.len() {
+        let delta = positions[i] - center;
+        let distance = delta.length().max(0.01);
+        let gravity_force = delta.normalized() * (distance * gravity_strength);
+        forces[i] -= gravity_force;
+    }
+
+    let mut new_positions = positions.to_vec();
+    // Update positions based on forces
+    for i in 0..graph.len() {
+        let force_magnitude = forces[i].length();
+
+        let movement = if force_magnitude > max_movement {
+            forces[i] * (max_movement / force_magnitude)
+        } else {
+            forces[i]
+        };
+        new_positions[i] += movement * c;
+    }
+
+    let total_change: f32 = forces.iter().map(|f| f.length()).sum();
+    (new_positions, total_change)
+}
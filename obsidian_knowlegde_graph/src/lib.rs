@@ -0,0 +1,20 @@
+// The graph model and algorithms, shared by the viewer and the kgraph command line tool
+pub mod analysis;
+pub mod camera;
+pub mod commands;
+pub mod data;
+pub mod export;
+pub mod geometry;
+pub mod import;
+pub mod labels;
+pub mod layout;
+pub mod link_report;
+pub mod minimap;
+pub mod navigation;
+pub mod paths;
+pub mod render;
+pub mod scene;
+pub mod similarity;
+pub mod source;
+pub mod stats;
+pub mod timeline;
@@ -0,0 +1,62 @@
+use crate::data::Graph;
+use crate::stats::{self, NodeRef, Unresolved};
+use eframe::egui;
+
+// Panel listing notes nothing links to and link targets that aren't notes
+pub struct LinkReport {
+    pub open: bool,
+    // The node clicked in the list, it gets a ring around it in the graph
+    pub focused: Option<usize>,
+    orphans: Vec<NodeRef>,
+    unresolved: Vec<Unresolved>,
+}
+
+impl LinkReport {
+    pub fn new(graph: &Graph) -> Self {
+        Self {
+            open: false,
+            focused: None,
+            orphans: stats::orphans(graph),
+            unresolved: stats::unresolved(graph),
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Link report");
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::CollapsingHeader::new(format!("Orphans ({})", self.orphans.len()))
+                .default_open(true)
+                .show(ui, |ui| {
+                    if self.orphans.is_empty() {
+                        ui.label("Every note is linked");
+                    }
+                    for node in &self.orphans {
+                        focus_label(ui, &mut self.focused, node);
+                    }
+                });
+            egui::CollapsingHeader::new(format!("Unresolved links ({})", self.unresolved.len()))
+                .default_open(true)
+                .show(ui, |ui| {
+                    if self.unresolved.is_empty() {
+                        ui.label("Every link goes to a note");
+                    }
+                    for entry in &self.unresolved {
+                        focus_label(ui, &mut self.focused, &entry.target);
+                        ui.indent(entry.target.id, |ui| {
+                            for source in &entry.referenced_by {
+                                focus_label(ui, &mut self.focused, source);
+                            }
+                        });
+                    }
+                });
+        });
+    }
+}
+
+fn focus_label(ui: &mut egui::Ui, focused: &mut Option<usize>, node: &NodeRef) {
+    let is_focused = *focused == Some(node.id);
+    let title = node.title.trim_end_matches(".md");
+    if ui.selectable_label(is_focused, title).clicked() {
+        *focused = if is_focused { None } else { Some(node.id) };
+    }
+}
@@ -1,13 +1,24 @@
-mod data;
-use crate::data::LinkNode;
-use data::{data, lockbookdata, Graph};
+use clap::Parser;
 use eframe::egui;
-use egui::ahash::{HashMap, HashMapExt};
 use egui::epaint::Shape;
-use egui::{Align2, Color32, FontId, Painter, Pos2, Stroke, Vec2};
+use egui::{Align2, Color32, Painter, Pos2, Rect, Stroke, Vec2};
+use obsidian_knowlegde_graph::analysis::{is_one_way, prepare};
+use obsidian_knowlegde_graph::camera::Camera;
+use obsidian_knowlegde_graph::commands::{Action, CommandPalette};
+use obsidian_knowlegde_graph::data::Graph;
+use obsidian_knowlegde_graph::export::ExportMenu;
+use obsidian_knowlegde_graph::geometry::arrow_points;
+use obsidian_knowlegde_graph::labels::Labels;
+use obsidian_knowlegde_graph::layout::{apply_spring_layout, initialize_positions, LayoutControl};
+use obsidian_knowlegde_graph::link_report::LinkReport;
+use obsidian_knowlegde_graph::minimap::Minimap;
+use obsidian_knowlegde_graph::navigation::Selection;
+use obsidian_knowlegde_graph::paths::PathTool;
+use obsidian_knowlegde_graph::scene::{Scene, View};
+use obsidian_knowlegde_graph::source::SourceArgs;
+use obsidian_knowlegde_graph::timeline::Timeline;
 use rayon::iter::Positions;
 // use std::collections::HashMap;
-use std::collections::VecDeque;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
 use std::time;
@@ -15,10 +26,15 @@ use std::time::Duration;
 use std::{f32, time::Instant};
 use std::{thread, usize};
 
-// The makes it the code runs faster making it into grids
-struct Grid {
-    cell_size: f32,
-    grid: HashMap<(i32, i32), Vec<usize>>,
+// Screen points a second the arrow keys move the graph
+const KEY_PAN_SPEED: f32 = 600.0;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Draws a knowledge graph of your Lockbook notes.", long_about = None)]
+/// CLI
+struct Cli {
+    #[command(flatten)]
+    source: SourceArgs,
 }
 
 // #[derive(Default)]
@@ -27,66 +43,35 @@ struct KnowledgeGraphApp {
     graph: Graph,
     positions: Vec<egui::Pos2>,
     forces: Vec<egui::Vec2>,
-    zoom_factor: f32,
-    last_zoom: f32,
-    pan: Vec2,
+    camera: Camera,
     last_screen_size: egui::Vec2,
     cursor_loc: egui::Vec2,
     debug: String,
-    is_dragging: bool,
-    last_drag_pos: Option<egui::Pos2>,
     layout_time: f64,
     graph_complete: bool,
     layout_started: bool,
     iteration: usize,
     running: bool,
-    directional_links: HashMap<usize, Vec<usize>>,
     animation: bool,
     timer: time::Instant,
     thread_positions: Arc<RwLock<Vec<Pos2>>>,
     frame_count: usize,
     fps: f32,
     last_fps_update: Instant,
-}
-
-impl Grid {
-    fn new(cell_size: f32) -> Self {
-        Self {
-            cell_size,
-            grid: HashMap::new(),
-        }
-    }
-
-    fn insert_node(&mut self, pos: egui::Pos2, index: usize) {
-        let grid_pos = self.get_grid_pos(pos);
-        self.grid
-            .entry(grid_pos)
-            .or_insert_with(Vec::new)
-            .push(index);
-    }
-
-    fn get_grid_pos(&self, pos: egui::Pos2) -> (i32, i32) {
-        let x = (pos.x / self.cell_size).floor() as i32;
-        let y = (pos.y / self.cell_size).floor() as i32;
-        (x, y)
-    }
-
-    fn get_neighboring_cells(&self, pos: egui::Pos2) -> Vec<&Vec<usize>> {
-        let grid_pos = self.get_grid_pos(pos);
-        let mut neighboring_cells = Vec::new();
-        for dx in -1..=1 {
-            for dy in -1..=1 {
-                if let Some(cell) = self.grid.get(&(grid_pos.0 + dx, grid_pos.1 + dy)) {
-                    neighboring_cells.push(cell);
-                }
-            }
-        }
-        neighboring_cells
-    }
-
-    fn clear(&mut self) {
-        self.grid.clear();
-    }
+    hovered: Option<usize>,
+    path_tool: PathTool,
+    export_menu: ExportMenu,
+    link_report: LinkReport,
+    all_arrows: bool,
+    layout: Arc<LayoutControl>,
+    scene: Scene,
+    labels: Labels,
+    minimap: Minimap,
+    selection: Selection,
+    palette: CommandPalette,
+    hide_unresolved: bool,
+    timeline: Timeline,
+    show_similar: bool,
 }
 
 impl KnowledgeGraphApp {
@@ -94,660 +79,319 @@ impl KnowledgeGraphApp {
         let positions = vec![egui::Pos2::ZERO; graph.len()];
         let thread_positions = vec![egui::Pos2::ZERO; graph.len()];
         let forces = vec![egui::Vec2::ZERO; graph.len()];
+        let link_report = LinkReport::new(&graph);
+        let labels = Labels::new(&graph);
+        let timeline = Timeline::new(&graph);
         Self {
             graph,
             positions,
             forces,
-            zoom_factor: 1.0,
-            last_zoom: 1.0,
-            pan: Vec2::ZERO,
+            camera: Camera::new(),
             last_screen_size: egui::Vec2::new(800.0, 600.0),
             cursor_loc: egui::Vec2::ZERO,
             debug: String::from("no single touch"),
-            is_dragging: false,
-            last_drag_pos: None,
             layout_time: 0.0,
             graph_complete: false,
             layout_started: false,
             iteration: 0,
             running: true,
-            directional_links: HashMap::new(),
             animation: true,
             timer: Instant::now(),
             thread_positions: Arc::new(RwLock::new(thread_positions)),
             frame_count: 0,
             fps: 0.0,
             last_fps_update: Instant::now(),
+            hovered: None,
+            path_tool: PathTool::new(),
+            export_menu: ExportMenu::new(),
+            link_report,
+            all_arrows: false,
+            layout: Arc::new(LayoutControl::default()),
+            scene: Scene::new(),
+            labels,
+            minimap: Minimap::new(),
+            selection: Selection::new(),
+            palette: CommandPalette::new(),
+            hide_unresolved: false,
+            timeline,
+            show_similar: true,
         }
     }
 
-    fn build_directional_links(&mut self) {
-        let mut directional_links = HashMap::new();
-
-        for node in &self.graph {
-            let mut directional = Vec::new();
-            for &link in &node.links {
-                if link < self.graph.len() {
-                    if !self.graph[link].links.contains(&node.id) {
-                        directional.push(link);
-                    }
-                }
-            }
-            directional_links.insert(node.id, directional);
-        }
-
-        self.directional_links = directional_links;
-    }
-
     fn initialize_positions(&mut self) {
-        let width = 800.0;
-        let height = 600.0;
-
-        let main_center = Pos2::new(width / 2.0, height / 2.0);
-
-        let cluster_small_radius = 10.0;
-
-        let mut positions_map = HashMap::new();
-        let mut clusters: HashMap<Option<usize>, Vec<usize>> = HashMap::new();
-        let mut unlinked_nodes: Vec<usize> = Vec::new();
-
-        for node in &self.graph {
-            if node.links.is_empty() {
-                unlinked_nodes.push(node.id);
-            } else {
-                clusters
-                    .entry(node.cluster_id)
-                    .or_insert_with(Vec::new)
-                    .push(node.id);
-            }
-        }
-
-        let mut largest_cluster_id: Option<usize> = None;
-        let mut largest_cluster_size: usize = 0;
-
-        for (cluster_id, node_ids) in &clusters {
-            if node_ids.len() > largest_cluster_size {
-                largest_cluster_size = node_ids.len();
-                largest_cluster_id = *cluster_id;
-            }
-        }
-
-        let num_multi_clusters = clusters.len();
-        let main_circle_radius = 200.0;
-
-        if num_multi_clusters > 0 {
-            let angle_step_clusters = 2.0 * std::f32::consts::PI / num_multi_clusters as f32;
-
-            for (cluster_id, node_ids) in clusters {
-                let number_nodes = node_ids.len();
-                let angle_step_nodes = 2.0 * std::f32::consts::PI / number_nodes as f32;
-                let mut count: f32 = 0.0;
-
-                let is_largest = Some(cluster_id.unwrap()) == largest_cluster_id;
-
-                let cluster_center = if is_largest {
-                    main_center
-                } else {
-                    let angle = cluster_id.unwrap() as f32 * angle_step_clusters;
-                    Pos2::new(
-                        main_center.x + main_circle_radius * angle.cos(),
-                        main_center.y + main_circle_radius * angle.sin(),
-                    )
-                };
-
-                for node_id in node_ids {
-                    let node_angle = count * angle_step_nodes;
-                    let node_pos = Pos2::new(
-                        cluster_center.x + cluster_small_radius * node_angle.cos(),
-                        cluster_center.y + cluster_small_radius * node_angle.sin(),
-                    );
-                    positions_map.insert(node_id, node_pos);
-                    count += 1.0;
-                }
-            }
-        }
-
-        let total_outer_nodes = unlinked_nodes.len();
-
-        if total_outer_nodes > 0 {
-            for (_i, &node_id) in unlinked_nodes.iter().enumerate() {
-                let nocluster: Option<usize> = None;
-                self.graph[node_id].cluster_id = nocluster;
-            }
-        }
-
-        self.positions = (0..self.graph.len())
-            .map(|i| *positions_map.get(&i).unwrap_or(&main_center))
-            .collect();
+        self.positions = initialize_positions(&mut self.graph);
         {
             let mut threadinfo = self.thread_positions.write().unwrap();
             *threadinfo = self.positions.clone();
         }
+        self.layout.generation.fetch_add(1, Ordering::Release);
     }
 
-    // Assuming Grid and LinkNode are defined elsewhere in your code
-    // struct Grid { ... }
-    // struct LinkNode { id: usize, links: Vec<usize>, /* other fields */ }
-
-    fn apply_spring_layout(
-        thread_positions: Arc<RwLock<Vec<Pos2>>>,
-        graph: &[LinkNode],
-        animation: bool,
-        max_iterations: usize,
-    ) {
-        let mut previous_postions: VecDeque<Vec<Pos2>> = VecDeque::new();
-        let width = 700.0;
-        let height = 500.0;
-        let num_nodes = graph.len() as f32;
-
-        // Spring and repulsion constants
-        let k_spring = 0.005;
-        let k_repel = 3.0;
-        let c = 0.05; // Scaling factor for movement
-        let max_movement = 100.0;
-
-        // Gravity parameters
-        let gravity_strength = 0.0001; // Adjust as needed
-        let center = Pos2::new(width / 2.0, height / 2.0);
-        // let mut positions1: Vec<Pos2> = Vec::new();
-
-        for _n in 0..max_iterations {
-            let cell_size = (width * height / num_nodes).sqrt();
-            let mut grid = Grid::new(cell_size);
-
-            // Read current positions
-            let positions = {
-                let pos_lock = thread_positions.read().unwrap();
-                pos_lock.clone()
-            };
-
-            // Insert nodes into the grid for spatial partitioning
-            for (i, &pos) in positions.iter().enumerate() {
-                grid.insert_node(pos, i);
-            }
-
-            // Initialize forces
-            let mut forces = vec![Vec2::ZERO; graph.len()];
-
-            // Calculate repulsive forces
-            for i in 0..graph.len() {
-                let pos_i = positions[i];
-
-                for cell in grid.get_neighboring_cells(pos_i) {
-                    for &j in cell {
-                        if i != j {
-                            let delta = pos_i - positions[j];
-                            let distance = delta.length().max(0.01);
-
-                            // Repulsive force calculation (inverse quartic)
-                            let repulsive_force = k_repel / (distance * distance / 20.0);
-                            let repulsion = delta.normalized() * repulsive_force;
-
-                            forces[i] += repulsion;
-                            forces[j] -= repulsion;
-                        }
-                    }
-                }
-            }
-
-            // Calculate attractive forces
-            for node in graph {
-                for &link in &node.links {
-                    if link >= graph.len() {
-                        println!(
-                            "Warning: Node {} has a link to invalid node {}",
-                            node.id, link
-                        );
-                        continue;
-                    }
-
-                    let delta = positions[node.id] - positions[link];
-                    let distance = delta.length().max(0.01);
-
-                    // Attractive force calculation (custom formula)
-                    let attractive_force = k_spring * distance * (distance / 20.0) as f32;
-                    let attraction = delta.normalized() * attractive_force;
-
-                    forces[node.id] -= attraction;
-                    forces[link] += attraction;
-                }
-            }
-
-            // Apply gravity to pull nodes toward the center
-            for i in 0..graph.len() {
-                let delta = positions[i] - center;
-                let distance = delta.length().max(0.01);
-                let gravity_force = delta.normalized() * (distance * gravity_strength);
-                forces[i] -= gravity_force;
-            }
-
-            let mut gitter: HashMap<usize, Pos2> = HashMap::new();
-            let mut new_positions = positions.clone();
-            let mut count = -1;
-            // // Attempt to stop gittering of nodes
-            // if previous_postions.len() == 100 {
-            //     if _n > 100000 {
-            //         for i in 0..new_positions.len() {
-            //             let mut sum = Pos2::new(0.0, 0.0);
-            //             let mut start = &previous_postions[1][i];
-            //             let mut last = &previous_postions[1][i];
-            //             for nodes_last in &previous_postions {
-            //                 sum += (nodes_last[i] - *last);
-            //                 if !(nodes_last[i] == *last) {
-            //                     last = &nodes_last[i];
-            //                 }
-            //             }
-            //             println!("{:?} is the value {:?}", graph[i].title, sum);
-            //             let var = 0.5;
-            //             if -var <= sum.x && sum.x <= var || -var <= sum.y && sum.y <= var {
-            //                 gitter.insert(i, sum);
-            //                 count += 1;
-            //                 println!(
-            //                     "{:?} innnnn side the no move zonnne {:?}",
-            //                     graph[i].title, sum
-            //                 );
-            //             }
-            //             // if sum.is_less_then(Pos2::new(1.0, 1.0)) {}
-            //         }
-            //         // println!("{:?}", previous_postions[4].len());
-            //         // for id in gitter {
-            //         //     let id=id.0;
-            //         //     new_positions[id] = previous_postions[4][id];
-            //         // }
-            //     }
-
-            //     previous_postions.pop_front();
-            //     // break;
-            // }
-            // Update positions based on forces
-            for i in 0..graph.len() {
-                let force_magnitude = forces[i].length();
-
-                let movement = if force_magnitude > max_movement {
-                    forces[i] * (max_movement / force_magnitude)
-                } else {
-                    forces[i]
-                };
-                if gitter.contains_key(&i) {
-                    new_positions[i] += movement * c;
-                }
-                new_positions[i] += movement * c;
-            }
-
-            // // Collision detection and resolution to prevent overlapping nodes
-            // let collision_cell_size = 2.0 * 20.0 + 5.0; // node_radius = 20.0, padding = 5.0
-            // let mut collision_grid = Grid::new(collision_cell_size);
-
-            // for (i, &pos) in new_positions.iter().enumerate() {
-            //     collision_grid.insert_node(pos, i);
-            // }
-
-            // for i in 0..graph.len() {
-            //     let pos_i = new_positions[i];
-
-            //     for cell in collision_grid.get_neighboring_cells(pos_i) {
-            //         for &j in cell {
-            //             if i != j {
-            //                 let delta = new_positions[i] - new_positions[j];
-            //                 let distance = delta.length();
-            //                 let min_distance = 2.0 * 20.0 + 5.0; // node_radius + padding
-
-            //                 if distance < min_distance {
-            //                     let overlap = min_distance - distance;
-            //                     let correction = delta.normalized() * (overlap / 2.0);
-            //                     new_positions[i] += correction;
-            //                     new_positions[j] -= correction;
-            //                 }
-            //             }
-            //         }
-            //     }
-            // }
-
-            // Write updated positions back to thread_positions
-
-            let clone_positions = positions.clone();
-            previous_postions.push_back(clone_positions);
-
-            {
-                let mut pos_lock = thread_positions.write().unwrap();
-                *pos_lock = new_positions.clone();
-            }
-
-            // Calculate total change for convergence
-            let total_change: f32 = forces.iter().map(|f| f.length()).sum();
-
-            // Debugging: Print iteration and total change
-            if _n % 1000 == 0 {
-                println!("Iteration: {}, Total Change: {:.4}", _n, total_change);
-            }
-
-            // Convergence check
-            if total_change < 0.01 * num_nodes || _n >= max_iterations {
-                println!(
-                    "Converged after {} iterations with total change {:.4}",
-                    _n, total_change
+    fn draw_graph(&mut self, ui: &mut egui::Ui, rect: egui::Rect) {
+        let view = View {
+            camera: self.camera,
+            arrows: self.all_arrows,
+            hide_unresolved: self.hide_unresolved,
+            moment: self.timeline.moment(),
+            similar: self.show_similar,
+        };
+        let generation = self.layout.generation();
+        self.scene
+            .update(&self.graph, &self.thread_positions, generation, view);
+        let scene = &self.scene;
+        let painter = &ui.painter_at(rect);
+
+        self.hovered = scene.node_at(self.cursor_loc.to_pos2());
+        scene.paint_edges(painter);
+
+        // Highlight the paths from the path finder, the selected one is drawn last so it stays on top
+        let path_color = Color32::from_rgb(255, 165, 0);
+        for (n, path) in self.path_tool.paths.iter().enumerate() {
+            if n != self.path_tool.selected {
+                draw_path(
+                    painter,
+                    path,
+                    &scene.screen,
+                    Stroke::new(2.0 * self.camera.zoom, path_color.gamma_multiply(0.35)),
                 );
-                break;
             }
-            // if (_n == 10) {
-            //     break;
-            // }
-
-            println!("Current Iteration: {}", _n);
-            grid.clear();
         }
-    }
-
-    fn draw_graph(&mut self, ui: &mut egui::Ui, screen_size: egui::Vec2) {
-        let center = Pos2::new(screen_size.x / 2.0, screen_size.y / 2.0);
-        let radius = (15.0) / ((self.graph.len() as f32).sqrt() / 3.0).max(1.0);
-        let positions = {
-            let pos_lock = self.thread_positions.read().unwrap();
-            pos_lock.clone()
-        };
-        // println!("running");
+        if let Some(path) = self.path_tool.selected_path() {
+            draw_path(
+                painter,
+                path,
+                &scene.screen,
+                Stroke::new(3.0 * self.camera.zoom, path_color),
+            );
+        }
 
-        let base_size = radius;
-        let k = 1.0;
-        let mut drawinfo: Option<(&Painter, Pos2, Pos2, Color32, f32, f32, f32)> = None;
-        let mut drawingstuf: Option<(usize, &LinkNode)> = None;
-        let node_sizes: Vec<f32> = self
-            .graph
-            .iter()
-            .map(|node| {
-                let n = node.links.len() as f32;
-                base_size + k * (n + 3.0).sqrt() * self.zoom_factor
-            })
-            .collect();
+        scene.paint_nodes(painter);
 
-        let transformed_positions: Vec<Pos2> = positions
-            .iter()
-            .map(|pos| {
-                let zoomed = (pos.to_vec2() - center.to_vec2()) * self.zoom_factor;
-                let panned = zoomed + self.pan;
-                (center.to_vec2() + panned).to_pos2()
-            })
-            .collect();
-        let mut hoveredvalue = self.graph.len() + 1;
-        for (i, node) in self.graph.iter().enumerate() {
-            let size = node_sizes[i];
-            let pos = transformed_positions[i];
-            if node_sizes[i] > 5.0 && cursorin(self.cursor_loc, pos, size) {
-                hoveredvalue = i;
+        // The few nodes that stand out get drawn over the batched ones
+        if let Some(path) = self.path_tool.selected_path() {
+            for &i in path.iter().filter(|&&i| scene.is_visible(i)) {
+                painter.circle_stroke(
+                    scene.screen[i],
+                    scene.sizes[i],
+                    Stroke::new(2.0 * self.camera.zoom, path_color),
+                );
             }
         }
-        for (i, node) in self.graph.iter().enumerate() {
-            for &link in &node.links {
-                if let Some(&target_pos) = transformed_positions.get(link) {
-                    let size = node_sizes[i];
-                    let pos = transformed_positions[i];
-                    let target = target_pos;
-                    let target_size = node_sizes[link];
-
-                    if self.has_directed_link(node.id, self.graph[link].id)
-                        && node_sizes[i] > 5.0
-                        && cursorin(self.cursor_loc, pos, size)
-                    {
-                        drawingstuf = Some((i, node));
-                        // drawinfo = Some((
-                        //     ui.painter(),
-                        //     pos,
-                        //     target,
-                        //     Color32::from_rgba_unmultiplied(66, 135, 245, 150), // Semi-transparent blue
-                        //     self.zoom_factor,
-                        //     target_size,
-                        //     size,
-                        // ));
-                        // draw_arrow(
-                        //     ui.painter(),
-                        //     pos,
-                        //     target,
-                        //     Color32::from_rgba_unmultiplied(66, 135, 245, 150), // Semi-transparent blue
-                        //     self.zoom_factor,
-                        //     target_size,
-                        // );
-                    } else if link == hoveredvalue {
-                    } else {
-                        ui.painter().line_segment(
-                            [pos, target],
-                            Stroke::new(1.0 * self.zoom_factor, Color32::GRAY),
-                        );
-                    }
-                }
-            }
+        if let Some(i) = self.link_report.focused.filter(|&i| scene.is_visible(i)) {
+            painter.circle_stroke(
+                scene.screen[i],
+                scene.sizes[i] + 4.0 * self.camera.zoom,
+                Stroke::new(2.0 * self.camera.zoom, Color32::WHITE),
+            );
         }
 
-        let mut text_info: Option<(Pos2, Align2, String, FontId, Color32)> = None;
-        for (i, node) in self.graph.iter().enumerate() {
-            let rgb_color = Color32::from_rgb(
-                (node.color[0] * 255.0) as u8,
-                (node.color[1] * 255.0) as u8,
-                (node.color[2] * 255.0) as u8,
+        // The selected node gets a yellow ring and the neighbour ] and [ are on a thinner one
+        let selected_color = Color32::from_rgb(255, 220, 0);
+        if let Some(i) = self.selection.node.filter(|&i| scene.is_visible(i)) {
+            painter.circle_stroke(
+                scene.screen[i],
+                scene.sizes[i] + 4.0 * self.camera.zoom,
+                Stroke::new(2.0 * self.camera.zoom, selected_color),
             );
-
-            let size = node_sizes[i];
-            let mut text_color = Color32::BLACK;
-            let mut text = node.title.clone();
-            if node.title.ends_with(".md") {
-                text_color = Color32::LIGHT_BLUE;
-                text = node.title.trim_end_matches(".md").to_string();
-            }
-            if node.cluster_id.is_some() {
-                let pos = transformed_positions[i];
-
-                ui.painter().circle(
-                    pos,
-                    size,
-                    rgb_color,
-                    Stroke::new(0.75 * self.zoom_factor, text_color),
-                );
-
-                if size > 5.0 && cursorin(self.cursor_loc, pos, size) {
-                    let font_id = egui::FontId::proportional(15.0 * (self.zoom_factor.sqrt())); // Adjust font size based on zoom
-                    text_info = Some((
-                        pos,
-                        egui::Align2::CENTER_CENTER,
-                        text,
-                        font_id,
-                        Color32::WHITE,
-                    ));
-                    // ui.painter().text(
-                    //     pos,
-                    //     egui::Align2::CENTER_CENTER,
-                    //     &text,
-                    //     font_id,
-                    //     Color32::WHITE,
-                    // );
-                }
-            }
         }
-        // if let Some((painter, pos, target, color, zoom_factor, size, self_size)) = drawinfo {
-        //     draw_arrow(painter, pos, target, color, zoom_factor, size, self_size);
-        // }
-        if let Some((i, node)) = drawingstuf {
-            for &link in &node.links {
-                if let Some(&target_pos) = transformed_positions.get(link) {
-                    let size = node_sizes[i];
-                    let pos = transformed_positions[i];
-                    let target = target_pos;
-                    let target_size = node_sizes[link];
+        if let Some(i) = self
+            .selection
+            .highlighted(&self.graph)
+            .filter(|&i| scene.is_visible(i))
+        {
+            painter.circle_stroke(
+                scene.screen[i],
+                scene.sizes[i] + 3.0 * self.camera.zoom,
+                Stroke::new(1.0 * self.camera.zoom, selected_color),
+            );
+        }
+
+        self.labels
+            .paint(painter, &self.graph, scene, self.camera.zoom, self.hovered);
 
-                    if self.has_directed_link(node.id, self.graph[link].id)
-                        && node_sizes[i] > 5.0
-                        && cursorin(self.cursor_loc, pos, size)
-                    {
+        if let Some(i) = self.hovered {
+            let node = &self.graph[i];
+            let pos = scene.screen[i];
+            // Without the arrow mode only the hovered node shows which way its links go
+            if !self.all_arrows {
+                for &link in &node.links {
+                    if link < scene.screen.len() && is_one_way(&self.graph, i, link) {
                         draw_arrow(
-                            ui.painter(),
+                            painter,
                             pos,
-                            target,
+                            scene.screen[link],
                             Color32::from_rgba_unmultiplied(66, 135, 245, 150), // Semi-transparent blue
-                            self.zoom_factor,
-                            target_size,
-                            size,
+                            self.camera.zoom,
+                            scene.sizes[link],
+                            scene.sizes[i],
                         );
                     }
                 }
             }
-        }
-        if let Some((pos, anchor, text, font_id, text_color)) = text_info {
-            ui.painter().text(pos, anchor, text, font_id, text_color);
+            if scene.is_visible(i) {
+                let font_id = egui::FontId::proportional(15.0 * (self.camera.zoom.sqrt())); // Adjust font size based on zoom
+                painter.text(
+                    pos,
+                    Align2::CENTER_CENTER,
+                    node.title.trim_end_matches(".md"),
+                    font_id,
+                    Color32::WHITE,
+                );
+            }
         }
 
-        self.last_screen_size = screen_size;
+        self.last_screen_size = rect.size();
     }
-    fn has_directed_link(&self, from_node: usize, to_node: usize) -> bool {
-        if let Some(links) = self.directional_links.get(&from_node) {
-            links.contains(&to_node)
+
+    // All the ways of moving the camera around, only while the pointer is over the graph
+    fn handle_input(&mut self, ctx: &egui::Context, response: &egui::Response) {
+        let dt = ctx.input(|i| i.stable_dt).min(0.1);
+        if response.dragged() {
+            self.camera.stop();
+            self.camera.pan_by(response.drag_delta());
+        } else if response.drag_stopped() {
+            self.camera.fling(ctx.input(|i| i.pointer.velocity()));
         } else {
-            false
+            self.camera.update(dt);
         }
-    }
-
-    fn zoomed(&mut self, zoom: f32) {
-        self.positions = self
-            .positions
-            .iter()
-            .map(|&pos| (self.cursor_loc + ((pos.to_vec2() - self.cursor_loc) * zoom)).to_pos2())
-            .collect();
-    }
-
-    fn label_subgraphs(&mut self) {
-        let mut bluecol = 1.0;
-        let mut redcol = 0.1;
-        let mut greencol = 0.5;
 
-        for i in 0..self.graph.len() {
-            if self.graph[i].color[2] == 0.0 {
-                if self.graph[i].links.is_empty() {
-                    self.graph[i].color = [1.0, 1.0, 1.0];
-                } else {
-                    self.dfs(i, bluecol, redcol, greencol);
-
-                    bluecol = (bluecol * 0.7 + 0.2) % 1.0;
-                    redcol = (redcol * 1.5 + 0.3) % 1.0;
-                    greencol = (greencol * 1.3 + 0.4) % 1.0;
-                }
+        // Arrow keys move the graph for as long as they are held, left alone while typing
+        if !ctx.wants_keyboard_input() {
+            let held = ctx.input(|i| {
+                let axis = |negative, positive| {
+                    i.key_down(positive) as i32 as f32 - i.key_down(negative) as i32 as f32
+                };
+                Vec2::new(
+                    axis(egui::Key::ArrowRight, egui::Key::ArrowLeft),
+                    axis(egui::Key::ArrowDown, egui::Key::ArrowUp),
+                )
+            });
+            if held != Vec2::ZERO {
+                self.camera.stop();
+                self.camera.pan_by(held * KEY_PAN_SPEED * dt);
             }
         }
-    }
-
-    fn label_clusters(&mut self) {
-        let mut node_ids: Vec<usize> = Vec::new();
 
-        for node in &self.graph {
-            node_ids.push(node.id);
+        if !response.hovered() {
+            return;
         }
-        let mut count = 1;
-        // println!("{}", node_ids.len());
-        for node_id in node_ids {
-            // println!("Node {}", node_id);
-            self.clusters(node_id, count);
-            count += 1;
+        let anchor = response
+            .hover_pos()
+            .unwrap_or(self.camera.viewport.center());
+        let (zoom, scroll) = ctx.input(|i| (i.zoom_delta(), i.raw_scroll_delta));
+        // Pinch and ctrl + scroll come in as zoom, a plain scroll moves the graph
+        if zoom != 1.0 {
+            self.camera.zoom_at(anchor, zoom);
+        } else if scroll != Vec2::ZERO {
+            self.camera.pan_by(scroll);
         }
-        // println!("{:?}", self.graph);
     }
 
-    fn clusters(&mut self, node_id: usize, cluster_id: usize) {
-        if self.graph[node_id].cluster_id.is_some() {
-            return;
-        }
-
-        self.graph[node_id].cluster_id = Some(cluster_id);
-        // println!("Node {} assigned to cluster {}", node_id, cluster_id);
-
-        let links = self.graph[node_id].links.clone();
-
-        for link in links {
-            if link != node_id {
-                self.clusters(link, cluster_id);
-            }
+    // Does what a shortcut or the command palette asked for
+    fn run(&mut self, ctx: &egui::Context, action: Action) {
+        // Keyboard zoom goes towards the mouse when it is over the graph
+        let anchor = ctx
+            .input(|i| i.pointer.hover_pos())
+            .filter(|&pos| self.camera.viewport.contains(pos))
+            .unwrap_or(self.camera.viewport.center());
+        match action {
+            Action::FitGraph => self.fit_to_graph(),
+            Action::ZoomIn => self.camera.zoom_at(anchor, 1.1),
+            Action::ZoomOut => self.camera.zoom_at(anchor, 1.0 / 1.1),
+            Action::NextNeighbour => {
+                self.selection.cycle(&self.graph, 1);
+                self.reveal(self.selection.highlighted(&self.graph));
+            }
+            Action::PreviousNeighbour => {
+                self.selection.cycle(&self.graph, -1);
+                self.reveal(self.selection.highlighted(&self.graph));
+            }
+            Action::FollowNeighbour => {
+                let node = self.selection.follow(&self.graph);
+                self.reveal(node);
+            }
+            Action::Back => {
+                let node = self.selection.back();
+                self.reveal(node);
+            }
+            Action::ClearSelection => self.selection.clear(),
+            Action::TogglePreview => {
+                self.selection.preview = !self.selection.preview && self.selection.node.is_some()
+            }
+            Action::ToggleArrows => self.all_arrows = !self.all_arrows,
+            Action::ToggleLabels => self.labels.show = !self.labels.show,
+            Action::ToggleMinimap => self.minimap.show = !self.minimap.show,
+            Action::ToggleUnresolved => self.hide_unresolved = !self.hide_unresolved,
+            Action::ToggleSimilar => self.show_similar = !self.show_similar,
+            Action::ToggleLayout => self.layout.toggle_pause(),
+            Action::TogglePathFinder => self.path_tool.open = !self.path_tool.open,
+            Action::ToggleLinkReport => self.link_report.open = !self.link_report.open,
+            Action::ToggleExport => self.export_menu.open = !self.export_menu.open,
+            Action::ToggleTimeline => self.timeline.open = !self.timeline.open,
+            Action::PlayTimeline => self.timeline.toggle_playback(),
+            Action::CommandPalette => self.palette.toggle(),
         }
     }
 
-    fn bidiretional(&mut self) {
-        let clonedgraph: &Graph = &self.graph.clone();
-        for nodes in clonedgraph {
-            let node: usize = nodes.id;
-            // println!("{:?}", nodes);
-            for link in &nodes.links {
-                if !clonedgraph[*link].links.contains(&node) {
-                    // println!("pushed");
-                    self.graph[*link].links.push(node)
-                }
-            }
+    // Moves the camera to a node picked from the keyboard if it isn't already on screen
+    fn reveal(&mut self, node: Option<usize>) {
+        let Some(node) = node else {
+            return;
+        };
+        if self.scene.is_visible(node) {
+            return;
+        }
+        if let Some(&pos) = self.thread_positions.read().unwrap().get(node) {
+            self.camera.stop();
+            self.camera.center = pos;
         }
     }
 
-    fn dfs(&mut self, node_id: usize, col: f32, redcol: f32, greencol: f32) {
-        let links_to_visit: Vec<usize> = {
-            let node = &self.graph[node_id];
-            node.links
+    // Zooms to show every drawn node
+    fn fit_to_graph(&mut self) {
+        let positions = self.thread_positions.read().unwrap();
+        let bounds = Rect::from_points(
+            &self
+                .graph
                 .iter()
-                .filter_map(|&id| {
-                    if self.graph[id].color[2] == 0.0 {
-                        Some(id)
-                    } else {
-                        None
-                    }
-                })
-                .collect()
-        };
-
-        // Update the color of the current node
-        self.graph[node_id].color[0] = redcol;
-        self.graph[node_id].color[1] = greencol;
-        self.graph[node_id].color[2] = col;
-
-        for id in links_to_visit {
-            self.graph[id].color[0] = redcol;
-            self.graph[id].color[1] = greencol;
-            self.graph[id].color[2] = col;
-            self.dfs(id, col, redcol, greencol);
-        }
+                .filter(|node| node.cluster_id.is_some())
+                .filter_map(|node| positions.get(node.id).copied())
+                .collect::<Vec<Pos2>>(),
+        );
+        self.camera.fit(bounds);
     }
 }
 
 impl eframe::App for KnowledgeGraphApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        ctx.input(|i| {
-            // let is_zoom_modifier = if cfg!(target_os = "macos") {
-            //     i.modifiers.mac_cmd
-            // } else {
-            //     i.modifiers.ctrl
-            // };
-
-            // if is_zoom_modifier {
-            self.zoom_factor *= i.zoom_delta();
-            // self.zoomed(i.zoom_delta());
-            self.debug = (self.zoom_factor).to_string();
-            // let scrolly = i.raw_scroll_delta.y;
-            let scroll = i.raw_scroll_delta.to_pos2();
-            self.pan += (scroll).to_vec2();
-            //println!("{:?}", scroll);
-            // if scroll != 0.0 {
-            //     self.zoom_factor *= 1.0 + scroll * 0.1;
-
-            //     self.zoom_factor = self.zoom_factor.clamp(0.5, 3.0);
-            // }
-            self.debug = (self.zoom_factor).to_string();
-            // }
-        });
-
-        if ctx.input(|i| i.pointer.primary_down()) {
-            if let Some(current_pos) = ctx.input(|i| i.pointer.interact_pos()) {
-                if !self.is_dragging {
-                    self.is_dragging = true;
-                    self.last_drag_pos = Some(current_pos);
-                } else if let Some(last_pos) = self.last_drag_pos {
-                    let delta = current_pos - last_pos;
-
-                    self.pan += delta / self.zoom_factor;
-                    self.last_drag_pos = Some(current_pos);
-                }
-            }
-        } else {
-            self.is_dragging = false;
-            self.last_drag_pos = None;
+        // The palette goes first so its enter and escape don't also reach the shortcuts
+        let mut actions: Vec<Action> = self.palette.ui(ctx).into_iter().collect();
+        actions.extend(Action::pressed(ctx));
+        for action in actions {
+            self.run(ctx, action);
+        }
+        if let Some(node) = self.selection.preview_ui(ctx, &self.graph) {
+            self.reveal(Some(node));
+        }
+        if self.path_tool.open {
+            egui::SidePanel::right("path_finder").show(ctx, |ui| {
+                self.path_tool.ui(ui, &self.graph);
+            });
+        }
+        if self.link_report.open {
+            egui::SidePanel::left("link_report").show(ctx, |ui| {
+                self.link_report.ui(ui);
+            });
+        }
+        if self.timeline.open {
+            egui::TopBottomPanel::bottom("timeline").show(ctx, |ui| {
+                self.timeline.ui(ui);
+            });
+        }
+        self.timeline.update(ctx.input(|i| i.stable_dt).min(0.1));
+        if self.export_menu.open {
+            self.export_menu
+                .ui(ctx, &self.graph, &self.thread_positions);
         }
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.with_layout(egui::Layout::top_down(egui::Align::RIGHT), |ui| {
@@ -788,9 +432,37 @@ impl eframe::App for KnowledgeGraphApp {
             });
             ui.heading("Knowledge Graph");
             ui.text_edit_singleline(&mut self.debug);
+            ui.horizontal(|ui| {
+                ui.toggle_value(&mut self.path_tool.open, "Path finder");
+                ui.toggle_value(&mut self.export_menu.open, "Export");
+                ui.toggle_value(&mut self.link_report.open, "Link report");
+                ui.toggle_value(&mut self.all_arrows, "Arrows");
+                ui.toggle_value(&mut self.labels.show, "Labels");
+                ui.toggle_value(&mut self.minimap.show, "Minimap");
+                ui.toggle_value(&mut self.timeline.open, "Timeline");
+                ui.toggle_value(&mut self.hide_unresolved, "Hide unresolved");
+                ui.toggle_value(&mut self.show_similar, "Similar");
+                if ui
+                    .selectable_label(self.layout.is_paused(), "Pause layout")
+                    .clicked()
+                {
+                    self.layout.toggle_pause();
+                }
+                if ui.button("Commands").clicked() {
+                    self.palette.toggle();
+                }
+            });
 
-            ui.label(format!("FPS: {:.2}", self.fps));
-            let screen_size = ui.available_size();
+            ui.horizontal(|ui| {
+                ui.label(format!("FPS: {:.2}", self.fps));
+                if ui.button("Fit").clicked() {
+                    self.fit_to_graph();
+                }
+            });
+            // Everything below the controls is the graph
+            let (rect, response) =
+                ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+            self.camera.viewport = rect;
 
             if !self.graph_complete {
                 println!("started");
@@ -804,9 +476,11 @@ impl eframe::App for KnowledgeGraphApp {
                 let forces = self.forces.clone();
                 let postions = self.positions.clone();
                 let graph = self.graph.clone();
+                let control = Arc::clone(&self.layout);
                 thread::spawn(move || {
-                    Self::apply_spring_layout(postioninfo, &graph, false, 2500000);
+                    apply_spring_layout(postioninfo, control, &graph, 2500000);
                 });
+                self.fit_to_graph();
                 println!("ok done");
                 // while !is_finished.load(Ordering::SeqCst) {
                 //     // ctx.request_repaint();
@@ -816,8 +490,29 @@ impl eframe::App for KnowledgeGraphApp {
                 // }
             }
 
-            self.draw_graph(ui, screen_size);
+            self.handle_input(ctx, &response);
+            self.draw_graph(ui, rect);
+            let generation = self.layout.generation();
+            self.minimap.ui(
+                ui,
+                rect,
+                &self.graph,
+                &self.thread_positions,
+                generation,
+                &mut self.camera,
+            );
             ctx.request_repaint();
+
+            // A click on a node while picking sets that end of the path, otherwise it selects it
+            if response.clicked() {
+                if let Some(node) = self.hovered {
+                    if self.path_tool.picking.is_some() {
+                        self.path_tool.pick(node);
+                    } else {
+                        self.selection.select(node);
+                    }
+                }
+            }
             // println!("is drawing again");
             // let mut time: Instant = Instant::now();
             // if !self.layout_started {
@@ -834,7 +529,7 @@ impl eframe::App for KnowledgeGraphApp {
 
             //     //     let app_ref = Arc::new(self.clone());
             //     //     move || {
-            //     //     Self::apply_spring_layout(self, postioninfo);
+            //     //     apply_spring_layout(self, postioninfo);
             //     // }); // println!("iteration {}", self.iteration);
             //     self.apply_spring_layout(postioninfo);
             // }
@@ -867,44 +562,6 @@ impl eframe::App for KnowledgeGraphApp {
                 self.cursor_loc = cursor.to_vec2();
             }
         });
-        if !self.running {
-            let pointer = ctx.input(|i| i.pointer.clone());
-            if pointer.any_down() {
-                if let Some(current_pos) = pointer.interact_pos() {
-                    if !self.is_dragging {
-                        self.is_dragging = true;
-                        self.last_drag_pos = Some(current_pos);
-                    } else if let Some(_last_pos) = self.last_drag_pos {
-                        // let delta = current_pos - last_pos;
-
-                        self.last_drag_pos = Some(current_pos);
-                    }
-                }
-            } else {
-                self.is_dragging = false;
-                self.last_drag_pos = None;
-            }
-
-            if let Some(touches) = ctx.input(|i| i.multi_touch()) {
-                self.debug = touches.num_touches.to_string();
-            }
-
-            let mut zoom = 1.0;
-            if ctx.input(|i| i.key_pressed(egui::Key::Equals)) {
-                self.zoom_factor *= 1.1;
-                zoom = 1.1;
-            }
-            if ctx.input(|i| i.key_pressed(egui::Key::Minus)) {
-                self.zoom_factor *= 0.9;
-                zoom = 0.9;
-            }
-            if zoom != 1.0 {
-                if let Some(cursor) = ctx.input(|i| i.pointer.hover_pos()) {
-                    self.cursor_loc = cursor.to_vec2();
-                    self.zoomed(zoom);
-                }
-            }
-        }
         if let Some(cursor) = ctx.input(|i| i.pointer.hover_pos()) {
             self.cursor_loc = cursor.to_vec2();
         }
@@ -912,7 +569,8 @@ impl eframe::App for KnowledgeGraphApp {
 }
 
 fn main() {
-    let graph = lockbookdata();
+    let cli = Cli::parse();
+    let graph = cli.source.load();
     let stop_flag = Arc::new(AtomicBool::new(false));
     let stop_flag_clone = Arc::clone(&stop_flag);
 
@@ -925,14 +583,8 @@ fn main() {
         }
     });
 
-    let graph = fix_graph(graph);
-
-    let mut app = KnowledgeGraphApp::new(graph);
-    app.build_directional_links();
-    // println!("{:?}", app.directional_links);
-    app.bidiretional();
-    app.label_clusters();
-    app.label_subgraphs();
+    let graph = prepare(graph);
+    let app = KnowledgeGraphApp::new(graph);
     stop_flag.store(true, Ordering::SeqCst);
     let native_options = eframe::NativeOptions::default();
     eframe::run_native(
@@ -943,11 +595,6 @@ fn main() {
     .unwrap();
 }
 
-fn fix_graph(mut graph: Vec<LinkNode>) -> Vec<LinkNode> {
-    graph.sort_by_key(|node| node.id);
-    graph
-}
-
 fn draw_arrow(
     painter: &Painter,
     from: Pos2,
@@ -957,38 +604,18 @@ fn draw_arrow(
     size: f32,
     self_size: f32,
 ) {
-    let intersect = intersectstuff(from, to, size);
-    let intersect2 = intersectstuff1(from, to, self_size);
-
-    let to = to - intersect.to_vec2();
-    let from = from - intersect2.to_vec2();
-    let arrow_length = 6.0 * zoom_factor;
-    let arrow_width = 4.0 * zoom_factor;
-
     let arrow_color = Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), 255);
 
-    let direction = to - from;
-    let distance = direction.length();
-
-    if distance == 0.0 {
+    let Some(arrow) = arrow_points(from, to, zoom_factor, size, self_size) else {
         return;
-    }
-
-    let dir = direction / distance;
-
-    let arrow_base = to - dir * arrow_length;
-
-    let perp = Vec2::new(-dir.y, dir.x);
-
-    let arrow_p1 = arrow_base + perp * (arrow_width / 2.0);
-    let arrow_p2 = arrow_base - perp * (arrow_width / 2.0);
+    };
 
     painter.line_segment(
-        [from, arrow_base],
+        [arrow.start, arrow.base],
         Stroke::new(1.0 * zoom_factor, arrow_color),
     );
 
-    let points = vec![to, arrow_p1, arrow_p2];
+    let points = vec![arrow.tip, arrow.left, arrow.right];
 
     painter.add(Shape::convex_polygon(
         points,
@@ -997,39 +624,8 @@ fn draw_arrow(
     ));
 }
 
-fn cursorin(cursor: Vec2, center: Pos2, size: f32) -> bool {
-    if cursor.x > (center.x - size) && (center.x + size) > cursor.x {
-        if cursor.y > (center.y - size) && (center.y + size) > cursor.y {
-            return true;
-        }
-    }
-    false
-}
-fn intersectstuff(from: Pos2, to: Pos2, size: f32) -> Pos2 {
-    let x = from.x - to.x;
-    let y = from.y - to.y;
-    let angle = (y / x).atan();
-    let new_x = size * angle.cos();
-    let new_y: f32 = size * angle.sin();
-    let mut intersect = Pos2::new(new_x, new_y);
-    if x < 0.0 {
-        intersect = intersect;
-    } else {
-        intersect = (Pos2::new(0.0, 0.0) - intersect.to_vec2());
-    }
-    intersect
-}
-fn intersectstuff1(from: Pos2, to: Pos2, size: f32) -> Pos2 {
-    let x = from.x - to.x;
-    let y = from.y - to.y;
-    let angle = (y / x).atan();
-    let new_x = size * angle.cos();
-    let new_y: f32 = size * angle.sin();
-    let mut intersect = Pos2::new(new_x, new_y);
-    if x > 0.0 {
-        intersect = intersect;
-    } else {
-        intersect = (Pos2::new(0.0, 0.0) - intersect.to_vec2());
+fn draw_path(painter: &Painter, path: &[usize], positions: &[Pos2], stroke: Stroke) {
+    for pair in path.windows(2) {
+        painter.line_segment([positions[pair[0]], positions[pair[1]]], stroke);
     }
-    intersect
 }
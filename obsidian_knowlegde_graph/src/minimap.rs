@@ -0,0 +1,129 @@
+use crate::camera::Camera;
+use crate::data::Graph;
+use eframe::egui;
+use egui::epaint::{Mesh, Shape};
+use egui::{Color32, Pos2, Rect, Rounding, Sense, Stroke, Vec2};
+use std::sync::RwLock;
+
+const SIZE: Vec2 = Vec2::new(200.0, 150.0);
+const MARGIN: f32 = 10.0;
+const DOT: f32 = 1.5;
+
+// Whole graph squeezed into a corner of the window with a box around the part the camera sees.
+// Clicking or dragging in it moves the camera there
+pub struct Minimap {
+    pub show: bool,
+    // The dots only change when the layout moves or the window is resized
+    key: Option<(usize, Rect)>,
+    mesh: Mesh,
+    // Layout space covered by the minimap
+    bounds: Rect,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Minimap {
+    pub fn new() -> Self {
+        Self {
+            show: true,
+            key: None,
+            mesh: Mesh::default(),
+            bounds: Rect::NOTHING,
+        }
+    }
+
+    // graph_rect is the part of the window the graph is drawn in, the minimap goes in its
+    // bottom right corner
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        graph_rect: Rect,
+        graph: &Graph,
+        positions: &RwLock<Vec<Pos2>>,
+        generation: usize,
+        camera: &mut Camera,
+    ) {
+        if !self.show {
+            return;
+        }
+        let rect = Rect::from_min_size(graph_rect.max - SIZE - Vec2::splat(MARGIN), SIZE);
+        if !graph_rect.contains_rect(rect) {
+            return;
+        }
+        if self.key != Some((generation, rect)) {
+            self.key = Some((generation, rect));
+            self.rebuild(rect, graph, positions);
+        }
+        if !self.bounds.is_finite() {
+            return;
+        }
+
+        let response = ui.interact(rect, ui.id().with("minimap"), Sense::click_and_drag());
+        if let Some(pointer) = response.interact_pointer_pos() {
+            if response.clicked() || response.dragged() {
+                camera.stop();
+                camera.center = self.to_world(rect, pointer);
+            }
+        }
+
+        let painter = ui.painter_at(rect);
+        painter.rect(
+            rect,
+            Rounding::same(4.0),
+            Color32::from_black_alpha(200),
+            Stroke::new(1.0, Color32::DARK_GRAY),
+        );
+        painter.add(Shape::mesh(self.mesh.clone()));
+
+        let seen = Rect::from_two_pos(
+            self.to_minimap(rect, camera.screen_to_world(camera.viewport.min)),
+            self.to_minimap(rect, camera.screen_to_world(camera.viewport.max)),
+        );
+        painter.rect_stroke(seen, Rounding::ZERO, Stroke::new(1.0, Color32::WHITE));
+    }
+
+    fn rebuild(&mut self, rect: Rect, graph: &Graph, positions: &RwLock<Vec<Pos2>>) {
+        let positions = positions.read().unwrap();
+        let drawn: Vec<usize> = graph
+            .iter()
+            .filter(|node| node.cluster_id.is_some() && node.id < positions.len())
+            .map(|node| node.id)
+            .collect();
+        self.bounds = Rect::from_points(&drawn.iter().map(|&id| positions[id]).collect::<Vec<_>>());
+
+        self.mesh.clear();
+        if !self.bounds.is_finite() {
+            return;
+        }
+        for &id in &drawn {
+            let node = &graph[id];
+            let color = Color32::from_rgb(
+                (node.color[0] * 255.0) as u8,
+                (node.color[1] * 255.0) as u8,
+                (node.color[2] * 255.0) as u8,
+            );
+            let center = self.to_minimap(rect, positions[id]);
+            self.mesh
+                .add_colored_rect(Rect::from_center_size(center, Vec2::splat(DOT)), color);
+        }
+    }
+
+    // Scale that fits the bounds in the minimap, keeping its shape
+    fn scale(&self, rect: Rect) -> f32 {
+        let inner = rect.shrink(MARGIN);
+        let size = self.bounds.size().max(Vec2::splat(1.0));
+        (inner.width() / size.x).min(inner.height() / size.y)
+    }
+
+    fn to_minimap(&self, rect: Rect, pos: Pos2) -> Pos2 {
+        rect.center() + (pos - self.bounds.center()) * self.scale(rect)
+    }
+
+    fn to_world(&self, rect: Rect, pos: Pos2) -> Pos2 {
+        self.bounds.center() + (pos - rect.center()) / self.scale(rect)
+    }
+}
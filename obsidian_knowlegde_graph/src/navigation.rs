@@ -0,0 +1,140 @@
+use crate::data::Graph;
+use eframe::egui;
+
+// How much of a note the preview shows before cutting it off
+const EXCERPT_LINES: usize = 8;
+const EXCERPT_CHARS: usize = 400;
+
+// The node picked with a click or the keyboard, and which of its neighbours [ and ] are on
+#[derive(Default)]
+pub struct Selection {
+    pub node: Option<usize>,
+    neighbour: usize,
+    // Nodes that were selected before, so following links can be undone
+    history: Vec<usize>,
+    pub preview: bool,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn select(&mut self, node: usize) {
+        if let Some(previous) = self.node.filter(|&previous| previous != node) {
+            self.history.push(previous);
+        }
+        self.node = Some(node);
+        self.neighbour = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.node = None;
+        self.neighbour = 0;
+        self.preview = false;
+    }
+
+    // Moves the neighbour cursor forwards or backwards, wrapping around at the ends
+    pub fn cycle(&mut self, graph: &Graph, step: isize) {
+        let Some(node) = self.node else {
+            return;
+        };
+        let count = graph[node].degree();
+        if count > 0 {
+            self.neighbour = (self.neighbour as isize + step).rem_euclid(count as isize) as usize;
+        }
+    }
+
+    // The neighbour the cursor is on, outgoing links come first and then backlinks
+    pub fn highlighted(&self, graph: &Graph) -> Option<usize> {
+        graph[self.node?].neighbours().nth(self.neighbour)
+    }
+
+    // Selects the highlighted neighbour
+    pub fn follow(&mut self, graph: &Graph) -> Option<usize> {
+        let next = self.highlighted(graph)?;
+        self.select(next);
+        Some(next)
+    }
+
+    // Goes back to the node selected before this one
+    pub fn back(&mut self) -> Option<usize> {
+        let previous = self.history.pop()?;
+        self.node = Some(previous);
+        self.neighbour = 0;
+        Some(previous)
+    }
+
+    // Window with the start of the selected note and its links and backlinks, clicking one
+    // selects it
+    pub fn preview_ui(&mut self, ctx: &egui::Context, graph: &Graph) -> Option<usize> {
+        let node = &graph[self.node?];
+        let mut open = self.preview;
+        let mut clicked = None;
+        egui::Window::new("Preview")
+            .open(&mut open)
+            .default_width(260.0)
+            .show(ctx, |ui| {
+                ui.heading(node.title.trim_end_matches(".md"));
+                if !node.resolved {
+                    ui.label("Nothing in the vault goes by this name");
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    if let Some(excerpt) = excerpt(&node.body) {
+                        ui.label(egui::RichText::new(excerpt).weak());
+                        ui.separator();
+                    }
+                    ui.label(format!("Links ({})", node.links.len()));
+                    for &link in &node.links {
+                        link_row(ui, graph, link, &mut clicked);
+                    }
+                    ui.separator();
+                    ui.label(format!("Backlinks ({})", node.backlinks.len()));
+                    for &link in &node.backlinks {
+                        link_row(ui, graph, link, &mut clicked);
+                    }
+                    if !node.similar.is_empty() {
+                        ui.separator();
+                        ui.label(format!("Similar ({})", node.similar.len()));
+                        for &link in &node.similar {
+                            link_row(ui, graph, link, &mut clicked);
+                        }
+                    }
+                });
+            });
+        self.preview = open;
+        if let Some(next) = clicked {
+            self.select(next);
+        }
+        clicked
+    }
+}
+
+// The first few lines of the note's text, cut at a word with … when there's more
+fn excerpt(body: &str) -> Option<String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+    let mut text = body
+        .lines()
+        .take(EXCERPT_LINES)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut cut = body.lines().count() > EXCERPT_LINES;
+    if let Some((end, _)) = text.char_indices().nth(EXCERPT_CHARS) {
+        let word = text[..end].rfind(char::is_whitespace).unwrap_or(end);
+        text.truncate(word);
+        cut = true;
+    }
+    if cut {
+        text.push('…');
+    }
+    Some(text)
+}
+
+fn link_row(ui: &mut egui::Ui, graph: &Graph, id: usize, clicked: &mut Option<usize>) {
+    if ui.link(graph[id].title.trim_end_matches(".md")).clicked() {
+        *clicked = Some(id);
+    }
+}
@@ -0,0 +1,350 @@
+use crate::analysis::adjacency;
+use crate::data::Graph;
+use eframe::egui;
+use egui::ahash::{HashSet, HashSetExt};
+use std::collections::VecDeque;
+
+// Which end of the path the next click on the graph will set
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PathEnd {
+    Start,
+    End,
+}
+
+// Everything the path panel needs to remember between frames
+pub struct PathTool {
+    pub open: bool,
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+    pub picking: Option<PathEnd>,
+    pub respect_direction: bool,
+    pub k: usize,
+    pub paths: Vec<Vec<usize>>,
+    pub selected: usize,
+    search_start: String,
+    search_end: String,
+    searched: bool,
+}
+
+impl Default for PathTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PathTool {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            start: None,
+            end: None,
+            picking: None,
+            respect_direction: false,
+            k: 1,
+            paths: Vec::new(),
+            selected: 0,
+            search_start: String::new(),
+            search_end: String::new(),
+            searched: false,
+        }
+    }
+
+    // Called when a node gets clicked while one of the pick buttons is active
+    pub fn pick(&mut self, node: usize) {
+        match self.picking.take() {
+            Some(PathEnd::Start) => self.start = Some(node),
+            Some(PathEnd::End) => self.end = Some(node),
+            None => return,
+        }
+        self.paths.clear();
+        self.searched = false;
+    }
+
+    // The path that is highlighted in the graph
+    pub fn selected_path(&self) -> Option<&Vec<usize>> {
+        self.paths.get(self.selected)
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, graph: &Graph) {
+        ui.heading("Path finder");
+
+        ui.label("From");
+        pick_row(
+            ui,
+            graph,
+            &mut self.start,
+            &mut self.search_start,
+            &mut self.picking,
+            PathEnd::Start,
+        );
+        ui.label("To");
+        pick_row(
+            ui,
+            graph,
+            &mut self.end,
+            &mut self.search_end,
+            &mut self.picking,
+            PathEnd::End,
+        );
+
+        ui.separator();
+        ui.checkbox(&mut self.respect_direction, "Respect link direction");
+        ui.add(egui::Slider::new(&mut self.k, 1..=10).text("paths"));
+
+        // A path from a note to itself is just the note, so don't offer to look for one
+        let same = self.start.is_some() && self.start == self.end;
+        if same {
+            self.paths.clear();
+            self.searched = false;
+            ui.label("Start and end are the same note, pick a different one");
+        }
+
+        if ui
+            .add_enabled(!same, egui::Button::new("Find path"))
+            .clicked()
+        {
+            if let (Some(start), Some(end)) = (self.start, self.end) {
+                let adjacency = adjacency(graph, self.respect_direction);
+                self.paths = k_shortest_paths(&adjacency, start, end, self.k);
+                self.selected = 0;
+                self.searched = true;
+            }
+        }
+
+        if self.searched && self.paths.is_empty() {
+            ui.label("These notes are not connected");
+        }
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (n, path) in self.paths.iter().enumerate() {
+                let heading = format!("Path {} ({} steps)", n + 1, path.len() - 1);
+                if ui.selectable_label(self.selected == n, heading).clicked() {
+                    self.selected = n;
+                }
+                // Only the notes in between the two ends are interesting
+                if path.len() > 2 {
+                    for &id in &path[1..path.len() - 1] {
+                        ui.label(format!("   {}", display_title(&graph[id].title)));
+                    }
+                } else {
+                    ui.label("   linked directly");
+                }
+            }
+        });
+    }
+}
+
+// One end of the path: the chosen note, a button to pick it with the mouse and a search box
+fn pick_row(
+    ui: &mut egui::Ui,
+    graph: &Graph,
+    chosen: &mut Option<usize>,
+    search: &mut String,
+    picking: &mut Option<PathEnd>,
+    end: PathEnd,
+) {
+    ui.horizontal(|ui| {
+        let name = chosen.map_or(String::from("(none)"), |id| {
+            display_title(&graph[id].title).to_string()
+        });
+        ui.label(name);
+        let picking_this = *picking == Some(end);
+        if ui.selectable_label(picking_this, "pick").clicked() {
+            *picking = if picking_this { None } else { Some(end) };
+        }
+    });
+    ui.text_edit_singleline(search);
+    if search.is_empty() {
+        return;
+    }
+    let needle = search.to_lowercase();
+    let mut picked = None;
+    for node in graph
+        .iter()
+        .filter(|node| node.title.to_lowercase().contains(&needle))
+        .take(8)
+    {
+        if ui.button(display_title(&node.title)).clicked() {
+            picked = Some(node.id);
+        }
+    }
+    if picked.is_some() {
+        *chosen = picked;
+        search.clear();
+    }
+}
+
+fn display_title(title: &str) -> &str {
+    title.trim_end_matches(".md")
+}
+
+// Breadth first search, every link counts as one step
+pub fn shortest_path(adjacency: &[Vec<usize>], from: usize, to: usize) -> Option<Vec<usize>> {
+    bfs(adjacency, from, to, &HashSet::new(), &HashSet::new())
+}
+
+// Yen's algorithm on top of the breadth first search, returns at most k loopless paths
+// ordered from shortest to longest
+pub fn k_shortest_paths(
+    adjacency: &[Vec<usize>],
+    from: usize,
+    to: usize,
+    k: usize,
+) -> Vec<Vec<usize>> {
+    let mut found: Vec<Vec<usize>> = Vec::new();
+    let Some(first) = shortest_path(adjacency, from, to) else {
+        return found;
+    };
+    found.push(first);
+    let mut candidates: Vec<Vec<usize>> = Vec::new();
+
+    while found.len() < k {
+        let last = found.last().unwrap().clone();
+        for spur_index in 0..last.len() - 1 {
+            let spur_node = last[spur_index];
+            let root = &last[..=spur_index];
+
+            // Stop the search from reusing an edge that another path with the same root took
+            let mut banned_edges = HashSet::new();
+            for path in &found {
+                if path.len() > spur_index + 1 && &path[..=spur_index] == root {
+                    banned_edges.insert((path[spur_index], path[spur_index + 1]));
+                }
+            }
+            // and from going back through the root
+            let banned_nodes: HashSet<usize> = root[..spur_index].iter().copied().collect();
+
+            if let Some(spur) = bfs(adjacency, spur_node, to, &banned_nodes, &banned_edges) {
+                let mut candidate = root[..spur_index].to_vec();
+                candidate.extend(spur);
+                if !found.contains(&candidate) && !candidates.contains(&candidate) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            break;
+        }
+        let shortest = candidates
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, path)| path.len())
+            .map(|(index, _)| index)
+            .unwrap();
+        found.push(candidates.remove(shortest));
+    }
+
+    found
+}
+
+fn bfs(
+    adjacency: &[Vec<usize>],
+    from: usize,
+    to: usize,
+    banned_nodes: &HashSet<usize>,
+    banned_edges: &HashSet<(usize, usize)>,
+) -> Option<Vec<usize>> {
+    if from >= adjacency.len() || to >= adjacency.len() {
+        return None;
+    }
+    let mut previous = vec![usize::MAX; adjacency.len()];
+    let mut visited = vec![false; adjacency.len()];
+    let mut queue = VecDeque::new();
+    visited[from] = true;
+    queue.push_back(from);
+
+    while let Some(node) = queue.pop_front() {
+        if node == to {
+            let mut path = vec![to];
+            let mut current = to;
+            while current != from {
+                current = previous[current];
+                path.push(current);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &next in &adjacency[node] {
+            if visited[next] || banned_nodes.contains(&next) || banned_edges.contains(&(node, next))
+            {
+                continue;
+            }
+            visited[next] = true;
+            previous[next] = node;
+            queue.push_back(next);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::LinkNode;
+
+    fn graph(links: &[&[usize]]) -> Graph {
+        links
+            .iter()
+            .enumerate()
+            .map(|(id, links)| LinkNode::new(id, format!("{}.md", id), links.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn shortest_path_takes_fewest_links() {
+        // 0 -> 1 -> 2 -> 3 and 0 -> 4 -> 3
+        let adjacency = adjacency(&graph(&[&[1, 4], &[2], &[3], &[], &[3]]), true);
+        assert_eq!(shortest_path(&adjacency, 0, 3), Some(vec![0, 4, 3]));
+        assert_eq!(shortest_path(&adjacency, 2, 2), Some(vec![2]));
+        assert_eq!(shortest_path(&adjacency, 3, 0), None);
+        assert_eq!(shortest_path(&adjacency, 0, 9), None);
+    }
+
+    #[test]
+    fn direction_only_matters_when_asked() {
+        // 0 -> 1 <- 2
+        let links = graph(&[&[1], &[], &[1]]);
+        assert_eq!(shortest_path(&adjacency(&links, true), 0, 2), None);
+        assert_eq!(
+            shortest_path(&adjacency(&links, false), 0, 2),
+            Some(vec![0, 1, 2])
+        );
+    }
+
+    #[test]
+    fn bfs_keeps_out_of_banned_nodes_and_edges() {
+        let adjacency = adjacency(&graph(&[&[1, 2], &[3], &[3], &[]]), true);
+        let none = HashSet::new();
+        let banned_node: HashSet<usize> = [1].into_iter().collect();
+        assert_eq!(
+            bfs(&adjacency, 0, 3, &banned_node, &HashSet::new()),
+            Some(vec![0, 2, 3])
+        );
+        let banned_edges: HashSet<(usize, usize)> = [(0, 1), (2, 3)].into_iter().collect();
+        assert_eq!(bfs(&adjacency, 0, 3, &none, &banned_edges), None);
+    }
+
+    #[test]
+    fn k_shortest_paths_are_loopless_and_in_order() {
+        // two paths of three nodes, one of four, and a loop back to the start
+        let adjacency = adjacency(&graph(&[&[1, 2, 4], &[3, 0], &[3], &[], &[5], &[3]]), true);
+        let paths = k_shortest_paths(&adjacency, 0, 3, 5);
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths[0], [0, 1, 3]);
+        assert_eq!(paths[1], [0, 2, 3]);
+        assert_eq!(paths[2], [0, 4, 5, 3]);
+        for path in &paths {
+            let unique: HashSet<_> = path.iter().collect();
+            assert_eq!(unique.len(), path.len());
+        }
+    }
+
+    #[test]
+    fn k_shortest_paths_edge_cases() {
+        let adjacency = adjacency(&graph(&[&[1], &[], &[]]), true);
+        assert_eq!(k_shortest_paths(&adjacency, 0, 1, 3), [vec![0, 1]]);
+        assert!(k_shortest_paths(&adjacency, 0, 2, 3).is_empty());
+        assert_eq!(k_shortest_paths(&adjacency, 1, 1, 3), [vec![1]]);
+    }
+}
@@ -0,0 +1,352 @@
+use crate::analysis::is_one_way;
+use crate::data::Graph;
+use crate::geometry::{arrow_points, base_radius, curved_arrow_points, node_size};
+use eframe::egui;
+use egui::{Color32, Pos2, Vec2};
+use resvg::{tiny_skia, usvg};
+use std::fmt;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum RenderFormat {
+    Svg,
+    Png,
+}
+
+impl RenderFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "svg" => Some(RenderFormat::Svg),
+            "png" => Some(RenderFormat::Png),
+            _ => None,
+        }
+    }
+}
+
+pub struct RenderOptions {
+    pub width: u32,
+    pub height: u32,
+    pub labels: bool,
+    // Draw links that go both ways as two curved arrows instead of one plain line
+    pub arrows: bool,
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    Io(std::io::Error),
+    Svg(usvg::Error),
+    Png(String),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Io(e) => write!(f, "{}", e),
+            RenderError::Svg(e) => write!(f, "couldn't read the drawn svg: {}", e),
+            RenderError::Png(msg) => write!(f, "couldn't make the png: {}", msg),
+        }
+    }
+}
+
+impl From<std::io::Error> for RenderError {
+    fn from(e: std::io::Error) -> Self {
+        RenderError::Io(e)
+    }
+}
+
+impl From<usvg::Error> for RenderError {
+    fn from(e: usvg::Error) -> Self {
+        RenderError::Svg(e)
+    }
+}
+
+// Same colors as the window uses
+const BACKGROUND: Color32 = Color32::from_rgb(27, 27, 27);
+const ARROW: Color32 = Color32::from_rgb(66, 135, 245);
+const MUTUAL: Color32 = Color32::from_rgb(80, 200, 120);
+const SIMILAR: Color32 = Color32::from_rgb(120, 90, 160);
+const MARGIN: f32 = 40.0;
+
+pub fn write_render(
+    path: &Path,
+    graph: &Graph,
+    positions: &[Pos2],
+    options: &RenderOptions,
+    format: RenderFormat,
+) -> Result<(), RenderError> {
+    let svg = render_svg(graph, positions, options);
+    match format {
+        RenderFormat::Svg => fs::write(path, svg)?,
+        RenderFormat::Png => fs::write(path, render_png(&svg, options)?)?,
+    }
+    Ok(())
+}
+
+// Draws the graph the way draw_graph does, scaled so the whole graph fits the image.
+// One way links get the arrow from draw_arrow, every note gets its label
+pub fn render_svg(graph: &Graph, positions: &[Pos2], options: &RenderOptions) -> String {
+    let width = options.width as f32;
+    let height = options.height as f32;
+
+    // Unlinked nodes are not drawn in the window either, so they don't count for the fit
+    let drawn: Vec<usize> = graph
+        .iter()
+        .filter(|node| node.cluster_id.is_some() && node.id < positions.len())
+        .map(|node| node.id)
+        .collect();
+    let mut min = Pos2::new(f32::INFINITY, f32::INFINITY);
+    let mut max = Pos2::new(f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for &id in &drawn {
+        min = min.min(positions[id]);
+        max = max.max(positions[id]);
+    }
+    let span = if drawn.is_empty() {
+        Vec2::splat(1.0)
+    } else {
+        (max - min).max(Vec2::splat(1.0))
+    };
+    let zoom_factor = ((width - 2.0 * MARGIN) / span.x)
+        .min((height - 2.0 * MARGIN) / span.y)
+        .max(0.01);
+    let offset = Vec2::new(width, height) / 2.0 - span * zoom_factor / 2.0;
+    let screen: Vec<Pos2> = positions
+        .iter()
+        .map(|&pos| ((pos - min) * zoom_factor + offset).to_pos2())
+        .collect();
+
+    let base_size = base_radius(graph.len());
+    let node_sizes: Vec<f32> = graph
+        .iter()
+        .map(|node| node_size(base_size, node.degree(), zoom_factor))
+        .collect();
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">",
+        options.width, options.height
+    );
+    let _ = writeln!(
+        out,
+        "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+        svg_color(BACKGROUND)
+    );
+
+    out.push_str("<g id=\"edges\">\n");
+    for node in graph {
+        if node.id >= screen.len() {
+            continue;
+        }
+        // Notes that read alike get a dashed line, once per pair
+        for &other in node.similar.iter().filter(|&&other| other > node.id) {
+            if other >= screen.len()
+                || node.cluster_id.is_none()
+                || graph[other].cluster_id.is_none()
+            {
+                continue;
+            }
+            let _ = writeln!(
+                out,
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\" stroke-dasharray=\"{}\"/>",
+                screen[node.id].x,
+                screen[node.id].y,
+                screen[other].x,
+                screen[other].y,
+                svg_color(SIMILAR),
+                zoom_factor,
+                (6.0 * zoom_factor).max(4.0)
+            );
+        }
+        for &link in &node.links {
+            if link >= screen.len() {
+                continue;
+            }
+            let from = screen[node.id];
+            let to = screen[link];
+            if is_one_way(graph, node.id, link) {
+                let Some(arrow) =
+                    arrow_points(from, to, zoom_factor, node_sizes[link], node_sizes[node.id])
+                else {
+                    continue;
+                };
+                let _ = writeln!(
+                    out,
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\"/>",
+                    arrow.start.x,
+                    arrow.start.y,
+                    arrow.base.x,
+                    arrow.base.y,
+                    svg_color(ARROW),
+                    zoom_factor
+                );
+                let _ = writeln!(
+                    out,
+                    "<polygon points=\"{},{} {},{} {},{}\" fill=\"{}\"/>",
+                    arrow.tip.x,
+                    arrow.tip.y,
+                    arrow.left.x,
+                    arrow.left.y,
+                    arrow.right.x,
+                    arrow.right.y,
+                    svg_color(ARROW)
+                );
+            } else if options.arrows {
+                let Some(arrow) = curved_arrow_points(
+                    from,
+                    to,
+                    zoom_factor,
+                    node_sizes[link],
+                    node_sizes[node.id],
+                    0.15,
+                ) else {
+                    continue;
+                };
+                let _ = writeln!(
+                    out,
+                    "<path d=\"M {} {} Q {} {} {} {}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>",
+                    arrow.start.x,
+                    arrow.start.y,
+                    arrow.control.x,
+                    arrow.control.y,
+                    arrow.base.x,
+                    arrow.base.y,
+                    svg_color(MUTUAL),
+                    zoom_factor
+                );
+                let _ = writeln!(
+                    out,
+                    "<polygon points=\"{},{} {},{} {},{}\" fill=\"{}\"/>",
+                    arrow.tip.x,
+                    arrow.tip.y,
+                    arrow.left.x,
+                    arrow.left.y,
+                    arrow.right.x,
+                    arrow.right.y,
+                    svg_color(MUTUAL)
+                );
+            } else if node.id > link && graph[link].links.contains(&node.id) {
+                // Mutual links only need one line
+                continue;
+            } else {
+                let _ = writeln!(
+                    out,
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\"/>",
+                    from.x,
+                    from.y,
+                    to.x,
+                    to.y,
+                    svg_color(Color32::GRAY),
+                    zoom_factor
+                );
+            }
+        }
+    }
+    out.push_str("</g>\n<g id=\"nodes\">\n");
+    for &id in &drawn {
+        let node = &graph[id];
+        let pos = screen[id];
+        let fill = Color32::from_rgb(
+            (node.color[0] * 255.0) as u8,
+            (node.color[1] * 255.0) as u8,
+            (node.color[2] * 255.0) as u8,
+        );
+        let outline = if node.title.ends_with(".md") {
+            Color32::LIGHT_BLUE
+        } else {
+            Color32::BLACK
+        };
+        if !node.resolved {
+            // Hollow with a dashed edge, same as the viewer draws links to missing notes
+            let dash = (node_sizes[id] * std::f32::consts::TAU / 24.0).max(zoom_factor);
+            let _ = writeln!(
+                out,
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" fill-opacity=\"0.25\" stroke=\"{}\" stroke-width=\"{}\" stroke-dasharray=\"{}\"/>",
+                pos.x,
+                pos.y,
+                node_sizes[id],
+                svg_color(fill),
+                svg_color(fill),
+                zoom_factor,
+                dash
+            );
+            continue;
+        }
+        let _ = writeln!(
+            out,
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"/>",
+            pos.x,
+            pos.y,
+            node_sizes[id],
+            svg_color(fill),
+            svg_color(outline),
+            0.75 * zoom_factor
+        );
+    }
+    out.push_str("</g>\n");
+
+    if options.labels {
+        let font_size = 15.0 * zoom_factor.sqrt();
+        let _ = writeln!(
+            out,
+            "<g id=\"labels\" font-family=\"sans-serif\" font-size=\"{}\" fill=\"white\" text-anchor=\"middle\" dominant-baseline=\"central\">",
+            font_size
+        );
+        for &id in &drawn {
+            let pos = screen[id];
+            let text = graph[id].title.trim_end_matches(".md");
+            let _ = writeln!(
+                out,
+                "<text x=\"{}\" y=\"{}\">{}</text>",
+                pos.x,
+                pos.y,
+                xml_escape(text)
+            );
+        }
+        out.push_str("</g>\n");
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+// Rasterizes the svg. Labels use the system fonts, on a machine without any they are left out
+pub fn render_png(svg: &str, options: &RenderOptions) -> Result<Vec<u8>, RenderError> {
+    let mut usvg_options = usvg::Options::default();
+    let fontdb = usvg_options.fontdb_mut();
+    fontdb.load_system_fonts();
+    // sans-serif means Arial to usvg, use any other sans font the system has when it's missing
+    let query = usvg::fontdb::Query {
+        families: &[usvg::fontdb::Family::SansSerif],
+        ..Default::default()
+    };
+    if fontdb.query(&query).is_none() {
+        let fallback = fontdb
+            .faces()
+            .flat_map(|face| face.families.iter())
+            .map(|(family, _)| family.clone())
+            .find(|family| family.contains("Sans") && !family.contains("Mono"));
+        if let Some(family) = fallback {
+            fontdb.set_sans_serif_family(family);
+        }
+    }
+    let tree = usvg::Tree::from_str(svg, &usvg_options)?;
+    let mut pixmap = tiny_skia::Pixmap::new(options.width, options.height)
+        .ok_or_else(|| RenderError::Png(String::from("image size can't be zero")))?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    pixmap
+        .encode_png()
+        .map_err(|e| RenderError::Png(e.to_string()))
+}
+
+fn svg_color(color: Color32) -> String {
+    format!("rgb({},{},{})", color.r(), color.g(), color.b())
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,343 @@
+use crate::analysis::is_one_way;
+use crate::camera::Camera;
+use crate::data::{Graph, LinkNode};
+use crate::geometry::{arrow_points, base_radius, curved_arrow_points, node_size};
+use crate::layout::Grid;
+use crate::timeline::Moment;
+use eframe::egui;
+use egui::epaint::{Mesh, Shape};
+use egui::{Color32, Painter, Pos2, Rect, Vec2};
+use std::f32::consts::TAU;
+use std::sync::RwLock;
+
+const ONE_WAY: Color32 = Color32::from_rgb(66, 135, 245);
+const MUTUAL: Color32 = Color32::from_rgb(80, 200, 120);
+const SIMILAR: Color32 = Color32::from_rgba_premultiplied(120, 90, 160, 160);
+
+// Everything about the window that changes where or how the graph gets drawn
+#[derive(Clone, Copy, PartialEq)]
+pub struct View {
+    pub camera: Camera,
+    pub arrows: bool,
+    // Leaves out placeholder nodes for links to notes that don't exist
+    pub hide_unresolved: bool,
+    // Set while the timeline is winding the graph back
+    pub moment: Option<Moment>,
+    // Dashed edges between notes that read alike
+    pub similar: bool,
+}
+
+impl View {
+    // How big a node is drawn compared to its full size, None when it isn't drawn at all.
+    // Nodes without links aren't drawn either
+    fn growth(&self, node: &LinkNode) -> Option<f32> {
+        if node.cluster_id.is_none() || (self.hide_unresolved && !node.resolved) {
+            return None;
+        }
+        self.moment.map_or(Some(1.0), |moment| moment.growth(node))
+    }
+}
+
+// Screen space copy of the graph. Positions, sizes, the hover grid and the two meshes the
+// graph is drawn with only get rebuilt when the layout moves or the view changes, so a still
+// graph costs two mesh copies a frame no matter how many nodes it has
+pub struct Scene {
+    key: Option<(usize, View)>,
+    pub screen: Vec<Pos2>,
+    pub sizes: Vec<f32>,
+    visible: Vec<bool>,
+    grid: Grid,
+    edges: Mesh,
+    nodes: Mesh,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self {
+            key: None,
+            screen: Vec::new(),
+            sizes: Vec::new(),
+            visible: Vec::new(),
+            grid: Grid::new(1.0),
+            edges: Mesh::default(),
+            nodes: Mesh::default(),
+        }
+    }
+
+    // generation goes up every time the layout writes new positions
+    pub fn update(
+        &mut self,
+        graph: &Graph,
+        positions: &RwLock<Vec<Pos2>>,
+        generation: usize,
+        view: View,
+    ) {
+        if self.key == Some((generation, view)) {
+            return;
+        }
+        self.key = Some((generation, view));
+
+        let camera = view.camera;
+        {
+            let positions = positions.read().unwrap();
+            self.screen.clear();
+            self.screen
+                .extend(positions.iter().map(|&pos| camera.world_to_screen(pos)));
+        }
+        let base_size = base_radius(graph.len());
+        self.sizes.clear();
+        self.sizes.extend(graph.iter().map(|node| {
+            node_size(base_size, node.degree(), camera.zoom) * view.growth(node).unwrap_or(0.0)
+        }));
+
+        // Anything filtered out or off screen isn't drawn
+        self.visible.clear();
+        self.visible.extend(graph.iter().map(|node| {
+            view.growth(node).is_some()
+                && node.id < self.screen.len()
+                && camera
+                    .viewport
+                    .expand(self.sizes[node.id])
+                    .contains(self.screen[node.id])
+        }));
+
+        let largest = self.sizes.iter().copied().fold(1.0, f32::max);
+        self.grid = Grid::new(largest);
+        for (id, &pos) in self.screen.iter().enumerate() {
+            if self.visible[id] {
+                self.grid.insert_node(pos, id);
+            }
+        }
+
+        self.build_edges(graph, &view);
+        self.build_nodes(graph, &view);
+    }
+
+    pub fn is_visible(&self, id: usize) -> bool {
+        self.visible.get(id).copied().unwrap_or(false)
+    }
+
+    // The node under the cursor, small nodes can't be hovered just like before
+    pub fn node_at(&self, cursor: Pos2) -> Option<usize> {
+        self.grid
+            .get_neighboring_cells(cursor)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|&id| {
+                let size = self.sizes[id];
+                let delta = cursor - self.screen[id];
+                size > 5.0 && delta.x.abs() < size && delta.y.abs() < size
+            })
+            .max()
+    }
+
+    pub fn paint_edges(&self, painter: &Painter) {
+        painter.add(Shape::mesh(self.edges.clone()));
+    }
+
+    pub fn paint_nodes(&self, painter: &Painter) {
+        painter.add(Shape::mesh(self.nodes.clone()));
+    }
+
+    fn build_edges(&mut self, graph: &Graph, view: &View) {
+        let zoom_factor = view.camera.zoom;
+        let mesh = &mut self.edges;
+        mesh.clear();
+        for node in graph {
+            if view.similar {
+                // Each pair lists the other, so it's drawn from the lower id only
+                for &other in node.similar.iter().filter(|&&other| other > node.id) {
+                    if other >= self.screen.len() || node.id >= self.screen.len() {
+                        continue;
+                    }
+                    if view.growth(node).is_none() || view.growth(&graph[other]).is_none() {
+                        continue;
+                    }
+                    let from = self.screen[node.id];
+                    let to = self.screen[other];
+                    if view
+                        .camera
+                        .viewport
+                        .intersects(Rect::from_two_pos(from, to))
+                    {
+                        add_dashed_line(mesh, from, to, zoom_factor, SIMILAR);
+                    }
+                }
+            }
+            for &link in &node.links {
+                if node.id >= self.screen.len() || link >= self.screen.len() {
+                    continue;
+                }
+                if view.growth(node).is_none() || view.growth(&graph[link]).is_none() {
+                    continue;
+                }
+                let from = self.screen[node.id];
+                let to = self.screen[link];
+                if !view
+                    .camera
+                    .viewport
+                    .intersects(Rect::from_two_pos(from, to))
+                {
+                    continue;
+                }
+                if !view.arrows {
+                    add_line(mesh, from, to, zoom_factor, Color32::GRAY);
+                } else if is_one_way(graph, node.id, link) {
+                    let Some(arrow) =
+                        arrow_points(from, to, zoom_factor, self.sizes[link], self.sizes[node.id])
+                    else {
+                        continue;
+                    };
+                    add_line(mesh, arrow.start, arrow.base, zoom_factor, ONE_WAY);
+                    add_triangle(mesh, [arrow.tip, arrow.left, arrow.right], ONE_WAY);
+                } else {
+                    let Some(arrow) = curved_arrow_points(
+                        from,
+                        to,
+                        zoom_factor,
+                        self.sizes[link],
+                        self.sizes[node.id],
+                        0.15,
+                    ) else {
+                        continue;
+                    };
+                    add_curve(
+                        mesh,
+                        [arrow.start, arrow.control, arrow.base],
+                        zoom_factor,
+                        MUTUAL,
+                    );
+                    add_triangle(mesh, [arrow.tip, arrow.left, arrow.right], MUTUAL);
+                }
+            }
+        }
+    }
+
+    fn build_nodes(&mut self, graph: &Graph, view: &View) {
+        let zoom_factor = view.camera.zoom;
+        let mesh = &mut self.nodes;
+        mesh.clear();
+        for node in graph.iter().filter(|node| self.visible[node.id]) {
+            let pos = self.screen[node.id];
+            let size = self.sizes[node.id];
+            let color = Color32::from_rgb(
+                (node.color[0] * 255.0) as u8,
+                (node.color[1] * 255.0) as u8,
+                (node.color[2] * 255.0) as u8,
+            );
+            if node.resolved {
+                let outline = if node.title.ends_with(".md") {
+                    Color32::LIGHT_BLUE
+                } else {
+                    Color32::BLACK
+                };
+                add_circle(mesh, pos, size, color);
+                add_ring(mesh, pos, size, 0.75 * zoom_factor, outline, false);
+            } else {
+                // Links to notes that don't exist are drawn hollow with a dashed edge
+                add_circle(mesh, pos, size, color.gamma_multiply(0.25));
+                add_ring(mesh, pos, size, zoom_factor, color, true);
+            }
+        }
+    }
+}
+
+// More segments for bigger circles, tiny ones don't need many to look round
+fn segments(radius: f32) -> usize {
+    ((radius * 0.75) as usize).clamp(8, 48) & !1
+}
+
+fn add_line(mesh: &mut Mesh, from: Pos2, to: Pos2, width: f32, color: Color32) {
+    let direction = (to - from).normalized();
+    let side = Vec2::new(-direction.y, direction.x) * (width / 2.0);
+    let first = mesh.vertices.len() as u32;
+    mesh.colored_vertex(from + side, color);
+    mesh.colored_vertex(from - side, color);
+    mesh.colored_vertex(to + side, color);
+    mesh.colored_vertex(to - side, color);
+    mesh.add_triangle(first, first + 1, first + 2);
+    mesh.add_triangle(first + 1, first + 2, first + 3);
+}
+
+// Dashes and gaps as long as six line widths, with a floor so zooming out doesn't turn
+// them into thousands of specks
+fn add_dashed_line(mesh: &mut Mesh, from: Pos2, to: Pos2, width: f32, color: Color32) {
+    let dash = (6.0 * width).max(4.0);
+    let length = from.distance(to);
+    let direction = (to - from) / length.max(f32::EPSILON);
+    let mut start = 0.0;
+    while start < length {
+        let end = (start + dash).min(length);
+        add_line(
+            mesh,
+            from + direction * start,
+            from + direction * end,
+            width,
+            color,
+        );
+        start += 2.0 * dash;
+    }
+}
+
+fn add_triangle(mesh: &mut Mesh, points: [Pos2; 3], color: Color32) {
+    let first = mesh.vertices.len() as u32;
+    for point in points {
+        mesh.colored_vertex(point, color);
+    }
+    mesh.add_triangle(first, first + 1, first + 2);
+}
+
+// Quadratic curve through the control point, cut into short straight pieces
+fn add_curve(mesh: &mut Mesh, points: [Pos2; 3], width: f32, color: Color32) {
+    let [start, control, end] = points;
+    let pieces = 12;
+    let mut last = start;
+    for n in 1..=pieces {
+        let t = n as f32 / pieces as f32;
+        let next = (start.to_vec2() * (1.0 - t) * (1.0 - t)
+            + control.to_vec2() * 2.0 * (1.0 - t) * t
+            + end.to_vec2() * t * t)
+            .to_pos2();
+        add_line(mesh, last, next, width, color);
+        last = next;
+    }
+}
+
+fn add_circle(mesh: &mut Mesh, center: Pos2, radius: f32, color: Color32) {
+    let segments = segments(radius);
+    let first = mesh.vertices.len() as u32;
+    mesh.colored_vertex(center, color);
+    for n in 0..segments {
+        let angle = n as f32 / segments as f32 * TAU;
+        mesh.colored_vertex(center + radius * Vec2::angled(angle), color);
+    }
+    for n in 0..segments as u32 {
+        mesh.add_triangle(first, first + 1 + n, first + 1 + (n + 1) % segments as u32);
+    }
+}
+
+// A ring centered on the edge of the circle, dashed leaves out every other segment
+fn add_ring(mesh: &mut Mesh, center: Pos2, radius: f32, width: f32, color: Color32, dashed: bool) {
+    let segments = segments(radius);
+    let inner = (radius - width / 2.0).max(0.0);
+    let outer = radius + width / 2.0;
+    let step = if dashed { 2 } else { 1 };
+    for n in (0..segments).step_by(step) {
+        let start = Vec2::angled(n as f32 / segments as f32 * TAU);
+        let end = Vec2::angled((n + 1) as f32 / segments as f32 * TAU);
+        let first = mesh.vertices.len() as u32;
+        mesh.colored_vertex(center + start * inner, color);
+        mesh.colored_vertex(center + start * outer, color);
+        mesh.colored_vertex(center + end * inner, color);
+        mesh.colored_vertex(center + end * outer, color);
+        mesh.add_triangle(first, first + 1, first + 2);
+        mesh.add_triangle(first + 1, first + 2, first + 3);
+    }
+}
@@ -0,0 +1,160 @@
+use crate::data::Graph;
+use crate::stats::NodeRef;
+use egui::ahash::{HashMap, HashMapExt};
+use serde::Serialize;
+
+// Notes need at least this cosine similarity to be called alike when no threshold is given
+pub const DEFAULT_THRESHOLD: f32 = 0.3;
+// A note that reads like half the vault only gets its closest matches
+const MAX_PER_NOTE: usize = 5;
+// Words in more than this share of the notes say nothing about what a note is about, and
+// skipping them keeps the pair counting from going quadratic
+const MAX_DOCUMENT_SHARE: f32 = 0.5;
+
+const STOP_WORDS: &[&str] = &[
+    "about", "after", "again", "all", "also", "and", "any", "are", "because", "been", "before",
+    "being", "but", "can", "could", "did", "does", "doing", "for", "from", "had", "has", "have",
+    "her", "here", "him", "his", "how", "into", "its", "just", "more", "most", "not", "now", "off",
+    "once", "only", "other", "our", "out", "over", "own", "same", "she", "should", "some", "such",
+    "than", "that", "the", "their", "them", "then", "there", "these", "they", "this", "those",
+    "through", "too", "under", "until", "very", "was", "were", "what", "when", "where", "which",
+    "while", "who", "whom", "why", "will", "with", "would", "you", "your",
+];
+
+// Two notes that read alike but don't link each other
+#[derive(Serialize)]
+pub struct Suggestion {
+    pub a: NodeRef,
+    pub b: NodeRef,
+    pub score: f32,
+}
+
+// Fills in the similar edges of every note. They sit next to the links instead of in them,
+// so degrees, stats and exports don't see them
+pub fn add_similar_edges(graph: &mut Graph, threshold: f32) {
+    let pairs = similar_pairs(graph, threshold);
+    for node in graph.iter_mut() {
+        node.similar.clear();
+    }
+    for (a, b, _) in pairs {
+        graph[a].similar.push(b);
+        graph[b].similar.push(a);
+    }
+}
+
+pub fn suggestions(graph: &Graph, threshold: f32) -> Vec<Suggestion> {
+    similar_pairs(graph, threshold)
+        .into_iter()
+        .map(|(a, b, score)| Suggestion {
+            a: NodeRef::new(&graph[a]),
+            b: NodeRef::new(&graph[b]),
+            score,
+        })
+        .collect()
+}
+
+// Pairs of notes whose TF-IDF vectors are at least threshold alike, most alike first.
+// Notes already linked in either direction are left out
+pub fn similar_pairs(graph: &Graph, threshold: f32) -> Vec<(usize, usize, f32)> {
+    let vectors = tf_idf(graph);
+
+    // Every note a word shows up in and how much it weighs there. Two notes only have a
+    // similarity above zero if they share a word, so only those pairs get added up
+    let mut postings: HashMap<usize, Vec<(usize, f32)>> = HashMap::new();
+    for (id, vector) in vectors.iter().enumerate() {
+        for &(word, weight) in vector {
+            postings.entry(word).or_default().push((id, weight));
+        }
+    }
+    let mut scores: HashMap<(usize, usize), f32> = HashMap::new();
+    for notes in postings.values() {
+        for (n, &(a, weight_a)) in notes.iter().enumerate() {
+            for &(b, weight_b) in &notes[n + 1..] {
+                *scores.entry((a, b)).or_default() += weight_a * weight_b;
+            }
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize, f32)> = scores
+        .into_iter()
+        .filter(|&(_, score)| score >= threshold)
+        .filter(|&((a, b), _)| !graph[a].links.contains(&b) && !graph[b].links.contains(&a))
+        .map(|((a, b), score)| (a, b, score))
+        .collect();
+    pairs.sort_by(|x, y| y.2.total_cmp(&x.2).then((x.0, x.1).cmp(&(y.0, y.1))));
+
+    let mut count = vec![0; graph.len()];
+    pairs.retain(|&(a, b, _)| {
+        if count[a] >= MAX_PER_NOTE || count[b] >= MAX_PER_NOTE {
+            return false;
+        }
+        count[a] += 1;
+        count[b] += 1;
+        true
+    });
+    pairs
+}
+
+// One sparse vector per node of (word, weight), scaled to length 1 so a dot product is the
+// cosine similarity. Nodes without text get an empty vector
+fn tf_idf(graph: &Graph) -> Vec<Vec<(usize, f32)>> {
+    let mut words: HashMap<String, usize> = HashMap::new();
+    let counts: Vec<HashMap<usize, f32>> = graph
+        .iter()
+        .map(|node| {
+            let mut count: HashMap<usize, f32> = HashMap::new();
+            for token in tokens(&node.body) {
+                let next = words.len();
+                let word = *words.entry(token).or_insert(next);
+                *count.entry(word).or_default() += 1.0;
+            }
+            count
+        })
+        .collect();
+
+    let documents = counts.iter().filter(|count| !count.is_empty()).count();
+    let mut frequency = vec![0usize; words.len()];
+    for count in &counts {
+        for &word in count.keys() {
+            frequency[word] += 1;
+        }
+    }
+    let too_common = (documents as f32 * MAX_DOCUMENT_SHARE).max(2.0) as usize;
+
+    counts
+        .into_iter()
+        .map(|count| {
+            let total: f32 = count.values().sum();
+            let mut vector: Vec<(usize, f32)> = count
+                .into_iter()
+                .filter(|&(word, _)| frequency[word] <= too_common)
+                .map(|(word, n)| {
+                    let idf = (documents as f32 / frequency[word] as f32).ln();
+                    (word, n / total * idf)
+                })
+                .collect();
+            let length = vector
+                .iter()
+                .map(|(_, weight)| weight * weight)
+                .sum::<f32>()
+                .sqrt();
+            if length > 0.0 {
+                for (_, weight) in &mut vector {
+                    *weight /= length;
+                }
+            }
+            vector
+        })
+        .collect()
+}
+
+// Lowercase words of three letters or more, leaving out numbers, common English filler and
+// whatever is in link addresses
+fn tokens(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split_whitespace()
+        .filter(|chunk| !chunk.contains("://") && !chunk.starts_with("lb:"))
+        .flat_map(|chunk| chunk.split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| word.chars().count() >= 3 && !word.chars().all(|c| c.is_numeric()))
+        .map(|word| word.to_lowercase())
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+}
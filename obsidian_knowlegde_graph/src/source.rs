@@ -0,0 +1,61 @@
+use crate::data::{data, lockbookdata, Graph};
+use crate::import::{import_graph, ImportFormat};
+use crate::similarity::add_similar_edges;
+use std::path::PathBuf;
+
+// Where the graph comes from, shared by the viewer and kgraph
+#[derive(clap::Args, Debug)]
+pub struct SourceArgs {
+    /// Use the built in test graph instead of the Lockbook notes
+    #[arg(long)]
+    pub fixture: bool,
+
+    /// Read the graph from a JSON, GraphML or CSV edge list file instead of Lockbook
+    #[arg(long)]
+    pub input: Option<PathBuf>,
+
+    /// Format of the input file, guessed from the file extension when left out
+    #[arg(long, value_enum)]
+    pub input_format: Option<ImportFormat>,
+
+    /// Add dashed "similar to" edges between notes whose text is alike, --similar=0.5 sets how
+    /// alike from 0 to 1
+    #[arg(
+        long,
+        value_name = "THRESHOLD",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0.3"
+    )]
+    pub similar: Option<f32>,
+}
+
+impl SourceArgs {
+    // Loads the graph, printing what went wrong and exiting when the input can't be read
+    pub fn load(&self) -> Graph {
+        let mut graph = self.read();
+        if let Some(threshold) = self.similar {
+            add_similar_edges(&mut graph, threshold);
+        }
+        graph
+    }
+
+    fn read(&self) -> Graph {
+        if let Some(input) = &self.input {
+            match import_graph(input, self.input_format) {
+                Ok((graph, report)) => {
+                    report.print();
+                    graph
+                }
+                Err(e) => {
+                    eprintln!("Couldn't read {}: {}", input.display(), e);
+                    std::process::exit(1);
+                }
+            }
+        } else if self.fixture {
+            data()
+        } else {
+            lockbookdata()
+        }
+    }
+}
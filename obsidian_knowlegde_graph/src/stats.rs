@@ -0,0 +1,176 @@
+use crate::analysis::adjacency;
+use crate::data::{Graph, LinkNode};
+use serde::Serialize;
+
+// These go by the outgoing links only, so they work on a graph before or after prepare
+
+#[derive(Serialize)]
+pub struct Summary {
+    pub nodes: usize,
+    pub notes: usize,
+    pub links: usize,
+    pub orphans: usize,
+    pub dangling_links: usize,
+    pub unresolved: usize,
+    pub components: usize,
+    pub largest_component: usize,
+}
+
+#[derive(Serialize)]
+pub struct NodeRef {
+    pub id: usize,
+    pub title: String,
+}
+
+#[derive(Serialize)]
+pub struct Dangling {
+    pub source: NodeRef,
+    pub target: NodeRef,
+}
+
+#[derive(Serialize)]
+pub struct Unresolved {
+    pub target: NodeRef,
+    pub referenced_by: Vec<NodeRef>,
+}
+
+#[derive(Serialize)]
+pub struct LinkCount {
+    pub id: usize,
+    pub title: String,
+    pub incoming: usize,
+    pub outgoing: usize,
+}
+
+impl NodeRef {
+    pub(crate) fn new(node: &LinkNode) -> Self {
+        Self {
+            id: node.id,
+            title: node.title.clone(),
+        }
+    }
+}
+
+pub fn summary(graph: &Graph) -> Summary {
+    let components = component_sizes(graph);
+    Summary {
+        nodes: graph.len(),
+        notes: graph.iter().filter(|node| is_note(node)).count(),
+        links: graph.iter().map(|node| node.links.len()).sum(),
+        orphans: orphans(graph).len(),
+        dangling_links: dangling(graph).len(),
+        unresolved: graph.iter().filter(|node| !node.resolved).count(),
+        components: components.len(),
+        largest_component: components.first().copied().unwrap_or(0),
+    }
+}
+
+// Nodes that don't link anywhere and that nothing links to
+pub fn orphans(graph: &Graph) -> Vec<NodeRef> {
+    let incoming = incoming_counts(graph);
+    graph
+        .iter()
+        .filter(|node| node.links.is_empty() && incoming[node.id] == 0)
+        .map(NodeRef::new)
+        .collect()
+}
+
+// Links to something that isn't a note, either an unresolved target or an id that isn't in
+// the graph at all
+pub fn dangling(graph: &Graph) -> Vec<Dangling> {
+    let mut found = Vec::new();
+    for node in graph {
+        for &link in &node.links {
+            let target = match graph.get(link) {
+                Some(target) if target.resolved => continue,
+                Some(target) => NodeRef::new(target),
+                None => NodeRef {
+                    id: link,
+                    title: String::new(),
+                },
+            };
+            found.push(Dangling {
+                source: NodeRef::new(node),
+                target,
+            });
+        }
+    }
+    found
+}
+
+// Every unresolved target together with the notes that link to it
+pub fn unresolved(graph: &Graph) -> Vec<Unresolved> {
+    graph
+        .iter()
+        .filter(|node| !node.resolved)
+        .map(|target| Unresolved {
+            target: NodeRef::new(target),
+            referenced_by: graph
+                .iter()
+                .filter(|node| node.links.contains(&target.id))
+                .map(NodeRef::new)
+                .collect(),
+        })
+        .collect()
+}
+
+// Nodes sorted by how many links point at them
+pub fn most_linked(graph: &Graph, limit: usize) -> Vec<LinkCount> {
+    let incoming = incoming_counts(graph);
+    let mut counts: Vec<LinkCount> = graph
+        .iter()
+        .map(|node| LinkCount {
+            id: node.id,
+            title: node.title.clone(),
+            incoming: incoming[node.id],
+            outgoing: node.links.len(),
+        })
+        .collect();
+    counts.sort_by(|a, b| b.incoming.cmp(&a.incoming).then(a.id.cmp(&b.id)));
+    counts.truncate(limit);
+    counts
+}
+
+// Sizes of the groups of nodes that are connected when direction is ignored, biggest first
+pub fn component_sizes(graph: &Graph) -> Vec<usize> {
+    let neighbours = adjacency(graph, false);
+
+    let mut seen = vec![false; graph.len()];
+    let mut sizes = Vec::new();
+    for start in 0..graph.len() {
+        if seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut stack = vec![start];
+        let mut size = 0;
+        while let Some(id) = stack.pop() {
+            size += 1;
+            for &next in &neighbours[id] {
+                if !seen[next] {
+                    seen[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        sizes.push(size);
+    }
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes
+}
+
+fn incoming_counts(graph: &Graph) -> Vec<usize> {
+    let mut incoming = vec![0; graph.len()];
+    for node in graph {
+        for &link in &node.links {
+            if link < graph.len() {
+                incoming[link] += 1;
+            }
+        }
+    }
+    incoming
+}
+
+fn is_note(node: &LinkNode) -> bool {
+    node.title.ends_with(".md")
+}
@@ -0,0 +1,165 @@
+use crate::analysis::has_creation_times;
+use crate::data::{Graph, LinkNode};
+use eframe::egui;
+
+const DAY: u64 = 24 * 60 * 60 * 1000;
+// Seconds a playback of the whole history takes at 1x
+const PLAYBACK: f32 = 30.0;
+
+// The point in time the graph is shown at. Nodes that appeared after `until` are left out and
+// the ones that appeared less than `fade` before it are still growing in
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Moment {
+    pub until: u64,
+    pub fade: u64,
+}
+
+impl Moment {
+    // How far grown in a node is, None while it doesn't exist yet. Nodes without a date have
+    // always been there
+    pub fn growth(&self, node: &LinkNode) -> Option<f32> {
+        let Some(appeared) = node.appeared else {
+            return Some(1.0);
+        };
+        if appeared > self.until {
+            return None;
+        }
+        Some(((self.until - appeared) as f32 / self.fade.max(1) as f32).min(1.0))
+    }
+}
+
+// Slider along the bottom that winds the graph back to how it looked on an earlier day, and
+// plays it forwards so notes pop up in the order they were written. Lockbook only keeps the
+// last modified time, so without creation times notes come up in the order they were last
+// changed and the slider says so
+pub struct Timeline {
+    pub open: bool,
+    playing: bool,
+    // Dates are creation times rather than last modified times
+    created: bool,
+    speed: f32,
+    // Dated nodes sorted by when they appeared, for counting how many are showing
+    dates: Vec<u64>,
+    first: u64,
+    last: u64,
+    // Kept as a float so slow playback still moves forward between frames
+    time: f64,
+}
+
+impl Timeline {
+    pub fn new(graph: &Graph) -> Self {
+        let mut dates: Vec<u64> = graph.iter().filter_map(|node| node.appeared).collect();
+        dates.sort_unstable();
+        let first = dates.first().copied().unwrap_or(0);
+        let last = dates.last().copied().unwrap_or(0);
+        Self {
+            open: false,
+            playing: false,
+            created: has_creation_times(graph),
+            speed: 1.0,
+            dates,
+            first,
+            last,
+            time: last as f64,
+        }
+    }
+
+    // What the scene should show, None when the timeline is closed so the whole graph is drawn
+    pub fn moment(&self) -> Option<Moment> {
+        if !self.open || self.dates.is_empty() {
+            return None;
+        }
+        // Fading in over a fiftieth of the history keeps playback smooth at any vault age
+        let fade = ((self.last - self.first) / 50).max(1);
+        Some(Moment {
+            until: self.time as u64,
+            fade,
+        })
+    }
+
+    // Moves playback along, returns true while it is still going
+    pub fn update(&mut self, dt: f32) -> bool {
+        if !self.open || !self.playing {
+            return false;
+        }
+        let span = (self.last - self.first).max(DAY) as f64;
+        self.time += span * (dt * self.speed / PLAYBACK) as f64;
+        if self.time >= self.last as f64 {
+            self.time = self.last as f64;
+            self.playing = false;
+        }
+        true
+    }
+
+    pub fn toggle_playback(&mut self) {
+        if self.dates.is_empty() {
+            return;
+        }
+        self.open = true;
+        self.playing = !self.playing;
+        // Playing from the end starts over
+        if self.playing && self.time as u64 >= self.last {
+            self.time = self.first as f64;
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        if self.dates.is_empty() {
+            ui.label("None of the notes in this graph have dates");
+            return;
+        }
+        ui.horizontal(|ui| {
+            let label = if self.playing { "Pause" } else { "Play" };
+            if ui.button(label).clicked() {
+                self.toggle_playback();
+            }
+            egui::ComboBox::from_id_source("timeline_speed")
+                .selected_text(format!("{}x", self.speed))
+                .width(50.0)
+                .show_ui(ui, |ui| {
+                    for speed in [0.25, 0.5, 1.0, 2.0, 4.0] {
+                        ui.selectable_value(&mut self.speed, speed, format!("{}x", speed));
+                    }
+                });
+
+            let until = self.time as u64;
+            let shown = self.dates.partition_point(|&date| date <= until);
+            let summary = format!(
+                "{} by {}  {} of {} notes",
+                if self.created { "Created" } else { "Modified" },
+                format_date(until),
+                shown,
+                self.dates.len()
+            );
+            let summary_width = 200.0;
+            ui.spacing_mut().slider_width = (ui.available_width() - summary_width).max(100.0);
+            let mut time = until;
+            let slider =
+                ui.add(egui::Slider::new(&mut time, self.first..=self.last).show_value(false));
+            if slider.changed() {
+                self.time = time as f64;
+                self.playing = false;
+            }
+            ui.label(summary);
+        });
+    }
+}
+
+// YYYY-MM-DD in UTC, from the days to civil date conversion in Howard Hinnant's date algorithms
+pub fn format_date(millis: u64) -> String {
+    let days = (millis / DAY) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}